@@ -0,0 +1,39 @@
+use super::Scores;
+
+/// The number of frames on each side of a frame
+/// whose scores are averaged to adapt its threshold
+pub const WINDOW: usize = 2;
+
+/// The default ratio between the score of a frame and the average score
+/// of the frames around it above which a scene change is detected
+pub const ADAPTIVE_THRESHOLD: f64 = 3.0;
+
+/// The fraction of the pixel difference threshold a frame must reach
+/// before it is considered at all, so that small spikes
+/// in otherwise static scenes are not mistaken for cuts
+pub const MIN_SCORE_FACTOR: f64 = 0.5;
+
+/// Returns the score of frame `frameno` relative to the average score
+/// of the frames around it, similar to the adaptive detector of `PySceneDetect`.
+///
+/// A ratio well above 1.0 means the frame stands out from its neighbours,
+/// which is the case for cuts but not for fast motion, where every frame
+/// has a high score.
+pub fn adaptive_score<S: Scores>(scores: &S, frameno: usize, min_score: f64) -> Option<f64> {
+    let score = scores.score(frameno)?;
+    if score < min_score {
+        return Some(0.0);
+    }
+
+    let neighbours = (frameno.saturating_sub(WINDOW)..frameno)
+        .chain((frameno + 1..).take(WINDOW))
+        .filter_map(|frameno| scores.score(frameno))
+        .collect::<Vec<_>>();
+    if neighbours.is_empty() {
+        return Some(f64::MAX);
+    }
+    let average = neighbours.iter().sum::<f64>() / neighbours.len() as f64;
+
+    // Avoid dividing by zero after perfectly static frames
+    Some(score / average.max(1.0))
+}
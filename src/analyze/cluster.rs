@@ -0,0 +1,133 @@
+//! Grouping scenes that look alike, such as recurring camera angles.
+
+use rav1e::prelude::{CastFromPrimitive, ChromaSampling, Frame, Pixel, Plane};
+
+use crate::decoder::VideoDetails;
+
+/// The number of cells along each edge of the grid a frame is averaged over
+const GRID: usize = 8;
+
+/// The mean luma, U and V of each cell of a frame, in 8-bit units.
+type Signature = [[u8; GRID * GRID]; 3];
+
+/// Records a signature of each frame while a clip is detected,
+/// from which similar scenes are clustered once the scene changes are final.
+pub struct SceneClusters {
+    video_details: VideoDetails,
+    signatures: Vec<Signature>,
+}
+
+impl SceneClusters {
+    pub const fn new(video_details: VideoDetails) -> Self {
+        Self {
+            video_details,
+            signatures: Vec::new(),
+        }
+    }
+
+    /// Records the signature of the next frame.
+    pub fn push<T: Pixel>(&mut self, frame: &Frame<T>) {
+        self.signatures.push(signature(frame, &self.video_details));
+    }
+
+    /// Returns the cluster of each scene starting at `scene_changes`.
+    ///
+    /// Each scene joins the cluster whose mean signature is closest to its own,
+    /// if that differs by at most `threshold` on average, or starts a new cluster.
+    /// Clusters are numbered in order of their first scene.
+    pub fn finish(&self, scene_changes: &[usize], threshold: f64) -> Vec<usize> {
+        let mut clusters: Vec<(Vec<f64>, usize)> = Vec::new();
+        let mut scene_clusters = Vec::with_capacity(scene_changes.len());
+        for (i, &start) in scene_changes.iter().enumerate() {
+            let end = scene_changes
+                .get(i + 1)
+                .copied()
+                .unwrap_or(self.signatures.len());
+            let scene = mean_signature(&self.signatures[start..end]);
+            let closest = clusters
+                .iter()
+                .map(|(centroid, _)| distance(centroid, &scene))
+                .enumerate()
+                .min_by(|(_, a), (_, b)| a.total_cmp(b))
+                .filter(|&(_, distance)| distance <= threshold);
+            if let Some((cluster, _)) = closest {
+                let (centroid, scenes) = &mut clusters[cluster];
+                *scenes += 1;
+                // Move the centroid towards the new scene, keeping it the mean of every scene
+                for (centroid, &value) in centroid.iter_mut().zip(&scene) {
+                    *centroid += (value - *centroid) / *scenes as f64;
+                }
+                scene_clusters.push(cluster);
+            } else {
+                scene_clusters.push(clusters.len());
+                clusters.push((scene, 1));
+            }
+        }
+        scene_clusters
+    }
+}
+
+/// The mean of `signatures`, flattened.
+fn mean_signature(signatures: &[Signature]) -> Vec<f64> {
+    let mut mean = vec![0.0; 3 * GRID * GRID];
+    for signature in signatures {
+        for (mean, &value) in mean.iter_mut().zip(signature.iter().flatten()) {
+            *mean += f64::from(value);
+        }
+    }
+    let frames = signatures.len().max(1) as f64;
+    for mean in &mut mean {
+        *mean /= frames;
+    }
+    mean
+}
+
+/// The mean absolute difference between two signatures.
+fn distance(a: &[f64], b: &[f64]) -> f64 {
+    a.iter().zip(b).map(|(a, b)| (a - b).abs()).sum::<f64>() / a.len().max(1) as f64
+}
+
+/// Averages each plane of `frame` over a `GRID` by `GRID` grid.
+/// Monochrome frames have neutral chroma.
+fn signature<T: Pixel>(frame: &Frame<T>, video_details: &VideoDetails) -> Signature {
+    let mut signature = [[128; GRID * GRID]; 3];
+    let planes = if video_details.chroma_sampling == ChromaSampling::Cs400 {
+        1
+    } else {
+        3
+    };
+    for (plane, cells) in frame.planes.iter().zip(signature.iter_mut()).take(planes) {
+        let means = grid_means(plane, GRID, GRID, video_details.bit_depth);
+        for (cell, mean) in cells.iter_mut().zip(means) {
+            *cell = mean.round().min(255.0) as u8;
+        }
+    }
+    signature
+}
+
+/// The mean of each cell of `plane` divided into a grid of `columns` by `rows`,
+/// in 8-bit units, row by row.
+pub fn grid_means<T: Pixel>(
+    plane: &Plane<T>,
+    columns: usize,
+    rows: usize,
+    bit_depth: usize,
+) -> Vec<f32> {
+    let (width, height) = (plane.cfg.width, plane.cfg.height);
+    let mut sums = vec![0u64; columns * rows];
+    let mut counts = vec![0u64; columns * rows];
+    for y in 0..height {
+        let row = plane.row(y as isize);
+        let cell_row = y * rows / height * columns;
+        for (x, &pixel) in row[..width].iter().enumerate() {
+            let cell = cell_row + x * columns / width;
+            sums[cell] += u64::from(u32::cast_from(pixel));
+            counts[cell] += 1;
+        }
+    }
+    let scale = f64::from(1u32 << (bit_depth - 8));
+    sums.iter()
+        .zip(&counts)
+        .map(|(&sum, &count)| (sum as f64 / count.max(1) as f64 / scale) as f32)
+        .collect()
+}
@@ -0,0 +1,157 @@
+use std::{io::Read, sync::Arc};
+
+use rav1e::prelude::{Frame, Pixel};
+
+use super::{Algorithm, Detector};
+use crate::{decoder::Decoder, DetectionOptions, SceneDetectionSpeed};
+
+/// A detector taking part in ensemble detection.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+pub enum EnsembleMember {
+    /// rav1e's standard detector
+    Standard,
+    /// `Algorithm::PixelDifference`
+    PixelDifference,
+    /// `Algorithm::Histogram`
+    Histogram,
+    /// `Algorithm::Adaptive`
+    Adaptive,
+    /// `Algorithm::Hsv`
+    Hsv,
+}
+
+impl EnsembleMember {
+    const ALL: [EnsembleMember; 5] = [
+        EnsembleMember::Standard,
+        EnsembleMember::PixelDifference,
+        EnsembleMember::Histogram,
+        EnsembleMember::Adaptive,
+        EnsembleMember::Hsv,
+    ];
+
+    /// The options to run this detector with, based on the ensemble's options.
+    fn options(self, opts: DetectionOptions) -> DetectionOptions {
+        let (analysis_speed, algorithm) = match self {
+            EnsembleMember::Standard => (SceneDetectionSpeed::Standard, opts.algorithm),
+            EnsembleMember::PixelDifference => {
+                (SceneDetectionSpeed::Fast, Algorithm::PixelDifference)
+            }
+            EnsembleMember::Histogram => (SceneDetectionSpeed::Fast, Algorithm::Histogram),
+            EnsembleMember::Adaptive => (SceneDetectionSpeed::Fast, Algorithm::Adaptive),
+            EnsembleMember::Hsv => (SceneDetectionSpeed::Fast, Algorithm::Hsv),
+        };
+        DetectionOptions {
+            analysis_speed,
+            algorithm,
+            ensemble: None,
+            ..opts
+        }
+    }
+}
+
+/// Options for running several detectors over the same frames
+/// and combining their decisions.
+///
+/// Each detector votes with its weight, a detector with a weight of 0
+/// is not run at all. A frame is a scene change if the detectors voting
+/// for it make up at least `min_agreement` of the total weight.
+#[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serialize", serde(default, deny_unknown_fields))]
+pub struct EnsembleOptions {
+    pub standard: f64,
+    pub pixel_difference: f64,
+    pub histogram: f64,
+    pub adaptive: f64,
+    pub hsv: f64,
+    /// The fraction of the total weight needed for a scene change.
+    /// The default of 0.5 is a majority vote.
+    pub min_agreement: f64,
+}
+
+impl EnsembleOptions {
+    const fn weight(&self, member: EnsembleMember) -> f64 {
+        match member {
+            EnsembleMember::Standard => self.standard,
+            EnsembleMember::PixelDifference => self.pixel_difference,
+            EnsembleMember::Histogram => self.histogram,
+            EnsembleMember::Adaptive => self.adaptive,
+            EnsembleMember::Hsv => self.hsv,
+        }
+    }
+}
+
+impl Default for EnsembleOptions {
+    fn default() -> Self {
+        EnsembleOptions {
+            standard: 1.0,
+            pixel_difference: 1.0,
+            histogram: 1.0,
+            adaptive: 0.0,
+            hsv: 0.0,
+            min_agreement: 0.5,
+        }
+    }
+}
+
+/// Runs several detectors on the same frames and combines their votes.
+pub struct EnsembleDetector<T: Pixel> {
+    members: Vec<(EnsembleMember, f64, Detector<T>)>,
+    min_agreement: f64,
+    /// The detectors that voted for the most recently analyzed frame
+    votes: Vec<EnsembleMember>,
+}
+
+impl<T: Pixel> EnsembleDetector<T> {
+    /// # Errors
+    ///
+    /// - If using a Vapoursynth script that contains an unsupported video format.
+    pub fn new<R: Read>(
+        dec: &mut Decoder<R>,
+        opts: DetectionOptions,
+        ensemble: EnsembleOptions,
+    ) -> anyhow::Result<Self> {
+        let mut members = Vec::new();
+        for member in EnsembleMember::ALL {
+            let weight = ensemble.weight(member);
+            if weight > 0.0 {
+                members.push((member, weight, Detector::new(dec, member.options(opts))?));
+            }
+        }
+        anyhow::ensure!(
+            !members.is_empty(),
+            "Ensemble detection requires at least one detector with a positive weight"
+        );
+
+        Ok(Self {
+            members,
+            min_agreement: ensemble.min_agreement,
+            votes: Vec::new(),
+        })
+    }
+
+    pub fn analyze_next_frame(
+        &mut self,
+        frame_set: &[&Arc<Frame<T>>],
+        input_frameno: u64,
+        previous_keyframe: u64,
+    ) -> bool {
+        self.votes.clear();
+        let mut total_weight = 0.0;
+        let mut voted_weight = 0.0;
+        for (member, weight, detector) in &mut self.members {
+            total_weight += *weight;
+            if detector.analyze_next_frame(frame_set, input_frameno, previous_keyframe) {
+                voted_weight += *weight;
+                self.votes.push(*member);
+            }
+        }
+        !self.votes.is_empty() && voted_weight >= total_weight * self.min_agreement
+    }
+
+    /// The detectors that voted for the most recently analyzed frame.
+    pub fn votes(&self) -> &[EnsembleMember] {
+        &self.votes
+    }
+}
@@ -0,0 +1,61 @@
+use std::cmp;
+
+use rav1e::prelude::{CastFromPrimitive, Frame, Pixel, Plane};
+
+/// Scores frames by the average absolute difference
+/// between the pixels of their downscaled luma planes.
+///
+/// This is a port of rav1e's fast scene detection mode.
+pub struct FastScorer<T: Pixel> {
+    downscale: fn(&Plane<T>) -> Plane<T>,
+    /// The downscaled luma plane of the most recently scored frame
+    previous: Option<(usize, Plane<T>)>,
+}
+
+impl<T: Pixel> FastScorer<T> {
+    pub fn new(width: usize, height: usize) -> Self {
+        let downscale = match cmp::min(width, height) {
+            0..=240 => Plane::downscale::<1>,
+            241..=480 => Plane::downscale::<2>,
+            481..=720 => Plane::downscale::<4>,
+            721..=1080 => Plane::downscale::<8>,
+            1081..=1600 => Plane::downscale::<16>,
+            _ => Plane::downscale::<32>,
+        };
+        Self {
+            downscale,
+            previous: None,
+        }
+    }
+
+    /// Returns the score of frame `frameno` compared to the frame before it.
+    ///
+    /// Frames are expected to be scored in order,
+    /// so that each downscaled plane only needs to be computed once.
+    pub fn score(&mut self, frameno: usize, previous: &Frame<T>, current: &Frame<T>) -> f64 {
+        let previous = match self.previous.take() {
+            Some((cached_frameno, plane)) if cached_frameno + 1 == frameno => plane,
+            _ => (self.downscale)(&previous.planes[0]),
+        };
+        let current = (self.downscale)(&current.planes[0]);
+        let delta = delta_in_planes(&previous, &current);
+        self.previous = Some((frameno, current));
+        delta
+    }
+}
+
+/// Calculates the average sum of absolute difference (SAD) per pixel between 2 planes
+fn delta_in_planes<T: Pixel>(plane1: &Plane<T>, plane2: &Plane<T>) -> f64 {
+    let delta: u64 = plane1
+        .rows_iter()
+        .zip(plane2.rows_iter())
+        .map(|(row1, row2)| {
+            row1.iter()
+                .zip(row2.iter())
+                .map(|(&p1, &p2)| u64::from(i32::cast_from(p1).abs_diff(i32::cast_from(p2))))
+                .sum::<u64>()
+        })
+        .sum();
+
+    delta as f64 / (plane1.cfg.width * plane1.cfg.height) as f64
+}
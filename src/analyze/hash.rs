@@ -0,0 +1,71 @@
+//! Perceptual hashes of scenes, for finding the same content in other clips.
+
+use rav1e::prelude::{Frame, Pixel};
+
+use super::cluster::grid_means;
+use crate::decoder::VideoDetails;
+
+/// The hash compares each of 8 rows of this many cells to its neighbour
+const COLUMNS: usize = 9;
+const ROWS: usize = 8;
+
+/// Records a downscaled luma plane of each frame while a clip is detected,
+/// from which each scene is hashed once the scene changes are final.
+pub struct SceneHashes {
+    bit_depth: usize,
+    grids: Vec<[f32; COLUMNS * ROWS]>,
+}
+
+impl SceneHashes {
+    pub const fn new(video_details: &VideoDetails) -> Self {
+        Self {
+            bit_depth: video_details.bit_depth,
+            grids: Vec::new(),
+        }
+    }
+
+    /// Records the next frame.
+    pub fn push<T: Pixel>(&mut self, frame: &Frame<T>) {
+        let mut grid = [0.0; COLUMNS * ROWS];
+        grid.copy_from_slice(&grid_means(&frame.planes[0], COLUMNS, ROWS, self.bit_depth));
+        self.grids.push(grid);
+    }
+
+    /// Returns the hash of each scene starting at `scene_changes`.
+    ///
+    /// Each scene is hashed from the mean of its downscaled frames,
+    /// so the hash barely changes if the scene starts or ends a few frames
+    /// earlier or later in another clip.
+    pub fn finish(&self, scene_changes: &[usize]) -> Vec<u64> {
+        scene_changes
+            .iter()
+            .enumerate()
+            .map(|(i, &start)| {
+                let end = scene_changes
+                    .get(i + 1)
+                    .copied()
+                    .unwrap_or(self.grids.len());
+                // Comparing sums gives the same hash as comparing means
+                let mut sum = [0.0; COLUMNS * ROWS];
+                for grid in &self.grids[start..end] {
+                    for (sum, &value) in sum.iter_mut().zip(grid) {
+                        *sum += value;
+                    }
+                }
+                difference_hash(&sum)
+            })
+            .collect()
+    }
+}
+
+/// Hashes a grid by whether each cell is darker than the cell to its right,
+/// which is not affected by the overall brightness or contrast.
+fn difference_hash(grid: &[f32; COLUMNS * ROWS]) -> u64 {
+    let mut hash = 0;
+    for row in grid.chunks_exact(COLUMNS) {
+        for pair in row.windows(2) {
+            hash = (hash << 1) | u64::from(pair[0] < pair[1]);
+        }
+    }
+    hash
+}
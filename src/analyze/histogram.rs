@@ -0,0 +1,75 @@
+use rav1e::prelude::{CastFromPrimitive, Frame, Pixel};
+
+/// The number of bins in each luma histogram
+pub const BINS: usize = 64;
+
+/// The default percentage of pixels that need to change bins
+/// between two frames for a scene change to be detected
+pub const HISTOGRAM_THRESHOLD: f64 = 30.0;
+
+/// The shift mapping a pixel value of `bit_depth` bits to its histogram bin.
+pub const fn histogram_shift(bit_depth: usize) -> usize {
+    bit_depth - BINS.trailing_zeros() as usize
+}
+
+/// Scores frames by the difference between their luma histograms.
+///
+/// Unlike pixel differences, this is not affected by motion within a scene,
+/// but it does not detect cuts between shots with similar brightness.
+pub struct HistogramScorer {
+    /// Shift mapping a pixel value to its bin
+    shift: usize,
+    /// The histogram of the most recently scored frame
+    previous: Option<(usize, Box<[u32; BINS]>)>,
+}
+
+impl HistogramScorer {
+    pub fn new(bit_depth: usize) -> Self {
+        Self {
+            shift: histogram_shift(bit_depth),
+            previous: None,
+        }
+    }
+
+    /// Returns the percentage of pixels in frame `frameno`
+    /// that would need to change bins to match the histogram of the frame before it.
+    ///
+    /// Frames are expected to be scored in order,
+    /// so that each histogram only needs to be computed once.
+    pub fn score<T: Pixel>(
+        &mut self,
+        frameno: usize,
+        previous: &Frame<T>,
+        current: &Frame<T>,
+    ) -> f64 {
+        let previous = match self.previous.take() {
+            Some((cached_frameno, histogram)) if cached_frameno + 1 == frameno => histogram,
+            _ => luma_histogram(previous, self.shift),
+        };
+        let current = luma_histogram(current, self.shift);
+        let pixels: u32 = current.iter().sum();
+        let difference: u32 = previous
+            .iter()
+            .zip(current.iter())
+            .map(|(&a, &b)| a.abs_diff(b))
+            .sum();
+        self.previous = Some((frameno, current));
+
+        // Every moved pixel is counted once in the bin it left
+        // and once in the bin it entered
+        f64::from(difference) * 50.0 / f64::from(pixels.max(1))
+    }
+}
+
+/// Counts the luma pixels of `frame` in each of `BINS` bins,
+/// where `shift` maps a pixel value to its bin.
+pub fn luma_histogram<T: Pixel>(frame: &Frame<T>, shift: usize) -> Box<[u32; BINS]> {
+    let mut histogram = Box::new([0; BINS]);
+    for row in frame.planes[0].rows_iter() {
+        for &pixel in row {
+            let bin = (u32::cast_from(pixel) >> shift) as usize;
+            histogram[bin.min(BINS - 1)] += 1;
+        }
+    }
+    histogram
+}
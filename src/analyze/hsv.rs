@@ -0,0 +1,158 @@
+use rav1e::prelude::{CastFromPrimitive, ChromaSampling, Frame, Pixel};
+
+use crate::HsvWeights;
+
+/// The default weighted average difference in 8-bit HSV values
+/// between two frames above which a scene change is detected
+pub const HSV_THRESHOLD: f64 = 27.0;
+
+/// The approximate number of samples taken along the shorter edge of each frame
+const SAMPLES: usize = 270;
+
+/// Scores frames by the weighted average difference
+/// between the hue, saturation and value of their pixels.
+///
+/// This detects cuts between shots with similar brightness
+/// but different colours, which luma-based algorithms miss.
+pub struct HsvScorer {
+    weights: HsvWeights,
+    bit_depth: usize,
+    /// Chroma subsampling shift, horizontally and vertically
+    shift: (usize, usize),
+    has_chroma: bool,
+    /// Distance between samples, in chroma pixels
+    step: usize,
+    /// The HSV samples of the most recently scored frame
+    previous: Option<(usize, Vec<[u8; 3]>)>,
+}
+
+impl HsvScorer {
+    pub fn new(
+        width: usize,
+        height: usize,
+        bit_depth: usize,
+        chroma_sampling: ChromaSampling,
+        weights: HsvWeights,
+    ) -> Self {
+        let (chroma_width, chroma_height) = chroma_sampling.get_chroma_dimensions(width, height);
+        let has_chroma = chroma_sampling != ChromaSampling::Cs400;
+        let short_edge = if has_chroma {
+            chroma_width.min(chroma_height)
+        } else {
+            width.min(height)
+        };
+        let shift = match chroma_sampling {
+            ChromaSampling::Cs420 => (1, 1),
+            ChromaSampling::Cs422 => (1, 0),
+            ChromaSampling::Cs444 | ChromaSampling::Cs400 => (0, 0),
+        };
+        Self {
+            weights,
+            bit_depth,
+            shift,
+            has_chroma,
+            step: (short_edge / SAMPLES).max(1),
+            previous: None,
+        }
+    }
+
+    /// Returns the weighted average difference between the HSV values
+    /// of frame `frameno` and the frame before it.
+    ///
+    /// Frames are expected to be scored in order,
+    /// so that each frame only needs to be converted once.
+    pub fn score<T: Pixel>(
+        &mut self,
+        frameno: usize,
+        previous: &Frame<T>,
+        current: &Frame<T>,
+    ) -> f64 {
+        let previous = match self.previous.take() {
+            Some((cached_frameno, samples)) if cached_frameno + 1 == frameno => samples,
+            _ => self.samples(previous),
+        };
+        let current = self.samples(current);
+
+        let mut deltas = [0u64; 3];
+        for (a, b) in previous.iter().zip(current.iter()) {
+            let hue_delta = u16::from(a[0].abs_diff(b[0]));
+            // Hue wraps around
+            deltas[0] += u64::from(hue_delta.min(256 - hue_delta));
+            deltas[1] += u64::from(a[1].abs_diff(b[1]));
+            deltas[2] += u64::from(a[2].abs_diff(b[2]));
+        }
+        self.previous = Some((frameno, current));
+
+        let samples = previous.len().max(1) as f64;
+        let weights = [
+            self.weights.hue,
+            self.weights.saturation,
+            self.weights.value,
+        ];
+        let weighted: f64 = deltas
+            .iter()
+            .zip(weights.iter())
+            .map(|(&delta, &weight)| delta as f64 / samples * weight)
+            .sum();
+        weighted / weights.iter().sum::<f64>().max(f64::EPSILON)
+    }
+
+    /// Samples the frame on a grid, converting each sample to 8-bit HSV.
+    fn samples<T: Pixel>(&self, frame: &Frame<T>) -> Vec<[u8; 3]> {
+        let depth_shift = self.bit_depth - 8;
+        let to_u8 = |pixel: T| (u32::cast_from(pixel) >> depth_shift).min(255) as u8;
+        let luma = &frame.planes[0];
+        let (rows, cols) = if self.has_chroma {
+            (frame.planes[1].cfg.height, frame.planes[1].cfg.width)
+        } else {
+            (luma.cfg.height, luma.cfg.width)
+        };
+
+        let mut samples = Vec::with_capacity((rows / self.step + 1) * (cols / self.step + 1));
+        for y in (0..rows).step_by(self.step) {
+            let luma_row = luma.row((y << self.shift.1) as isize);
+            for x in (0..cols).step_by(self.step) {
+                let y_value = to_u8(luma_row[x << self.shift.0]);
+                let (u, v) = if self.has_chroma {
+                    (
+                        to_u8(frame.planes[1].p(x, y)),
+                        to_u8(frame.planes[2].p(x, y)),
+                    )
+                } else {
+                    (128, 128)
+                };
+                samples.push(yuv_to_hsv(y_value, u, v));
+            }
+        }
+        samples
+    }
+}
+
+/// Converts a full range BT.601 YUV value to HSV,
+/// with hue scaled so that a full turn is 256.
+fn yuv_to_hsv(y: u8, u: u8, v: u8) -> [u8; 3] {
+    let y = f32::from(y);
+    let u = f32::from(u) - 128.0;
+    let v = f32::from(v) - 128.0;
+    let r = 1.402f32.mul_add(v, y).clamp(0.0, 255.0);
+    let g = 0.714_136f32
+        .mul_add(-v, 0.344_136f32.mul_add(-u, y))
+        .clamp(0.0, 255.0);
+    let b = 1.772f32.mul_add(u, y).clamp(0.0, 255.0);
+
+    let max = r.max(g).max(b);
+    let min = r.min(g).min(b);
+    let chroma = max - min;
+    let saturation = if max > 0.0 { chroma / max * 255.0 } else { 0.0 };
+    let hue = if chroma <= 0.0 {
+        0.0
+    } else if max <= r {
+        ((g - b) / chroma).rem_euclid(6.0)
+    } else if max <= g {
+        (b - r) / chroma + 2.0
+    } else {
+        (r - g) / chroma + 4.0
+    };
+
+    [(hue / 6.0 * 256.0) as u8, saturation as u8, max as u8]
+}
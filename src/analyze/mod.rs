@@ -0,0 +1,334 @@
+//! Scene change detection implemented in this crate,
+//! which, unlike rav1e's detector, exposes its thresholds.
+
+mod adaptive;
+mod cluster;
+mod ensemble;
+mod fast;
+mod hash;
+mod histogram;
+mod hsv;
+mod postprocess;
+mod representative;
+mod scores;
+
+use std::{collections::BTreeMap, io::Read, sync::Arc};
+
+use rav1e::prelude::{Frame, Pixel};
+
+#[cfg(feature = "image")]
+pub use self::representative::sharpness;
+use self::{
+    adaptive::ADAPTIVE_THRESHOLD,
+    ensemble::EnsembleDetector,
+    fast::FastScorer,
+    histogram::{HistogramScorer, HISTOGRAM_THRESHOLD},
+    hsv::{HsvScorer, HSV_THRESHOLD},
+};
+pub use self::{
+    cluster::SceneClusters,
+    ensemble::{EnsembleMember, EnsembleOptions},
+    hash::SceneHashes,
+    postprocess::PostProcessor,
+    representative::{RepresentativeFrame, RepresentativeFrames},
+    scores::{FrameScorer, FrameScores},
+};
+use crate::{
+    decoder::{Decoder, VideoDetails},
+    new_detector, DetectionOptions, SceneChangeDetector, SceneDetectionSpeed,
+};
+
+/// The maximum number of frames before and after a frame
+/// considered when checking whether it is part of a flash.
+const MAX_FLASH_WINDOW: usize = 5;
+
+/// The detector used by [`crate::detect_scene_changes`].
+pub enum Detector<T: Pixel> {
+    Rav1e(Box<SceneChangeDetector<T>>),
+    Native(NativeDetector<T>),
+    Ensemble(EnsembleDetector<T>),
+}
+
+impl<T: Pixel> Detector<T> {
+    /// # Errors
+    ///
+    /// - If using a Vapoursynth script that contains an unsupported video format.
+    pub fn new<R: Read>(dec: &mut Decoder<R>, opts: DetectionOptions) -> anyhow::Result<Self> {
+        if let Some(ensemble) = opts.ensemble {
+            return Ok(Detector::Ensemble(EnsembleDetector::new(
+                dec, opts, ensemble,
+            )?));
+        }
+        Ok(match opts.analysis_speed {
+            SceneDetectionSpeed::Standard => Detector::Rav1e(Box::new(new_detector(dec, opts)?)),
+            SceneDetectionSpeed::Fast => {
+                Detector::Native(NativeDetector::new(&dec.get_video_details()?, opts))
+            }
+        })
+    }
+
+    /// Runs keyframe detection on the next frame in the lookahead queue.
+    ///
+    /// `frame_set` starts at the frame before `input_frameno`,
+    /// in the same way as for rav1e's `SceneChangeDetector`.
+    pub fn analyze_next_frame(
+        &mut self,
+        frame_set: &[&Arc<Frame<T>>],
+        input_frameno: u64,
+        previous_keyframe: u64,
+    ) -> bool {
+        match self {
+            Detector::Rav1e(detector) => {
+                detector.analyze_next_frame(frame_set, input_frameno, previous_keyframe)
+            }
+            Detector::Native(detector) => detector.analyze_next_frame(
+                frame_set,
+                input_frameno as usize,
+                previous_keyframe as usize,
+            ),
+            Detector::Ensemble(detector) => {
+                detector.analyze_next_frame(frame_set, input_frameno, previous_keyframe)
+            }
+        }
+    }
+
+    /// For ensemble detection, the detectors that voted
+    /// for the most recently analyzed frame.
+    pub fn votes(&self) -> Option<&[EnsembleMember]> {
+        match self {
+            Detector::Ensemble(detector) => Some(detector.votes()),
+            _ => None,
+        }
+    }
+}
+
+/// Native detection algorithms, used with `SceneDetectionSpeed::Fast`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+pub enum Algorithm {
+    /// Average difference between the pixels of downscaled luma planes,
+    /// the same algorithm as rav1e's fast mode
+    #[default]
+    PixelDifference,
+    /// Difference between luma histograms,
+    /// which is not affected by motion within a scene
+    Histogram,
+    /// Pixel differences relative to those of the surrounding frames,
+    /// which avoids false detections during fast motion
+    Adaptive,
+    /// Differences in hue, saturation and value, weighted by
+    /// `DetectionOptions::hsv_weights`, which detects cuts between shots
+    /// with similar brightness but different colours
+    Hsv,
+}
+
+/// Detects scene changes by comparing a score for each frame
+/// against a configurable threshold.
+pub struct NativeDetector<T: Pixel> {
+    scorer: Scorer<T>,
+    /// The score of each frame compared to the frame before it,
+    /// for the frames around the one currently being analyzed
+    scores: BTreeMap<usize, f64>,
+    decider: Decider,
+}
+
+impl<T: Pixel> NativeDetector<T> {
+    pub fn new(video_details: &VideoDetails, opts: DetectionOptions) -> Self {
+        Self {
+            scorer: Scorer::new(video_details, opts),
+            scores: BTreeMap::new(),
+            decider: Decider::new(video_details.bit_depth, opts),
+        }
+    }
+
+    fn analyze_next_frame(
+        &mut self,
+        frame_set: &[&Arc<Frame<T>>],
+        input_frameno: usize,
+        previous_keyframe: usize,
+    ) -> bool {
+        // `frame_set[0]` is the frame before `input_frameno`
+        for (i, pair) in frame_set.windows(2).enumerate() {
+            let frameno = input_frameno + i;
+            if !self.scores.contains_key(&frameno) {
+                let score = self.scorer.score(frameno, pair[0], pair[1]);
+                self.scores.insert(frameno, score);
+            }
+        }
+        self.scores = self
+            .scores
+            .split_off(&input_frameno.saturating_sub(self.decider.history()));
+
+        self.decider
+            .is_scenecut(&self.scores, input_frameno, previous_keyframe)
+    }
+}
+
+enum Scorer<T: Pixel> {
+    PixelDifference(FastScorer<T>),
+    Histogram(HistogramScorer),
+    Hsv(HsvScorer),
+}
+
+impl<T: Pixel> Scorer<T> {
+    fn new(video_details: &VideoDetails, opts: DetectionOptions) -> Self {
+        match opts.algorithm {
+            Algorithm::PixelDifference | Algorithm::Adaptive => {
+                Scorer::PixelDifference(FastScorer::new(video_details.width, video_details.height))
+            }
+            Algorithm::Histogram => {
+                Scorer::Histogram(HistogramScorer::new(video_details.bit_depth))
+            }
+            Algorithm::Hsv => Scorer::Hsv(HsvScorer::new(
+                video_details.width,
+                video_details.height,
+                video_details.bit_depth,
+                video_details.chroma_sampling,
+                opts.hsv_weights,
+            )),
+        }
+    }
+
+    fn score(&mut self, frameno: usize, previous: &Frame<T>, current: &Frame<T>) -> f64 {
+        match self {
+            Scorer::PixelDifference(scorer) => scorer.score(frameno, previous, current),
+            Scorer::Histogram(scorer) => scorer.score(frameno, previous, current),
+            Scorer::Hsv(scorer) => scorer.score(frameno, previous, current),
+        }
+    }
+}
+
+/// The score of each frame compared to the frame before it.
+trait Scores {
+    fn score(&self, frameno: usize) -> Option<f64>;
+}
+
+impl Scores for BTreeMap<usize, f64> {
+    fn score(&self, frameno: usize) -> Option<f64> {
+        self.get(&frameno).copied()
+    }
+}
+
+/// Decides which frames are scene changes, based on the scores of the frames.
+#[derive(Debug, Clone, Copy)]
+struct Decider {
+    algorithm: Algorithm,
+    threshold: f64,
+    /// The minimum pixel difference for the adaptive algorithm
+    min_score: f64,
+    flash_window: usize,
+    min_scenecut_distance: usize,
+    max_scenecut_distance: usize,
+}
+
+impl Decider {
+    fn new(bit_depth: usize, opts: DetectionOptions) -> Self {
+        let pixel_threshold = opts.fast_pixel_threshold * bit_depth as f64 / 8.0;
+        let base_threshold = match opts.algorithm {
+            Algorithm::PixelDifference => pixel_threshold,
+            Algorithm::Histogram => HISTOGRAM_THRESHOLD,
+            Algorithm::Adaptive => ADAPTIVE_THRESHOLD,
+            Algorithm::Hsv => HSV_THRESHOLD,
+        };
+        Self {
+            algorithm: opts.algorithm,
+            threshold: base_threshold.mul_add(opts.threshold_scale, opts.threshold_bias),
+            min_score: pixel_threshold * adaptive::MIN_SCORE_FACTOR,
+            flash_window: if opts.detect_flashes {
+                opts.lookahead_distance.min(MAX_FLASH_WINDOW)
+            } else {
+                0
+            },
+            min_scenecut_distance: opts.min_scenecut_distance.unwrap_or(0),
+            max_scenecut_distance: opts.max_scenecut_distance.unwrap_or(usize::MAX),
+        }
+    }
+
+    /// The number of frames before the one being decided on
+    /// whose scores are needed for the decision
+    const fn history(&self) -> usize {
+        match self.algorithm {
+            Algorithm::Adaptive => self.flash_window + adaptive::WINDOW,
+            _ => self.flash_window,
+        }
+    }
+
+    /// Returns the score of `frameno` to compare against the threshold.
+    fn score<S: Scores>(&self, scores: &S, frameno: usize) -> Option<f64> {
+        match self.algorithm {
+            Algorithm::Adaptive => adaptive::adaptive_score(scores, frameno, self.min_score),
+            _ => scores.score(frameno),
+        }
+    }
+
+    fn is_scenecut<S: Scores>(&self, scores: &S, frameno: usize, previous_keyframe: usize) -> bool {
+        let distance = frameno - previous_keyframe;
+        if distance < self.min_scenecut_distance {
+            return false;
+        }
+        if distance >= self.max_scenecut_distance {
+            return true;
+        }
+
+        let Some(score) = self.score(scores, frameno) else {
+            return false;
+        };
+        if score < self.threshold {
+            return false;
+        }
+        let back = (frameno.saturating_sub(self.flash_window)..frameno)
+            .filter_map(|frameno| self.score(scores, frameno))
+            .collect::<Vec<_>>();
+        let forward = (frameno + 1..)
+            .take(self.flash_window)
+            .map_while(|frameno| self.score(scores, frameno))
+            .collect::<Vec<_>>();
+        is_scenecut(score, &back, &forward, self.threshold, self.flash_window)
+    }
+}
+
+/// Compares the score of a frame to the threshold,
+/// using the scores of up to `flash_window` frames before (`back`)
+/// and after (`forward`) it to avoid placing scene changes on short flashes.
+fn is_scenecut(
+    score: f64,
+    back: &[f64],
+    forward: &[f64],
+    threshold: f64,
+    flash_window: usize,
+) -> bool {
+    if score < threshold {
+        return false;
+    }
+    if flash_window == 0 {
+        return true;
+    }
+    if forward.len() + 1 < flash_window {
+        // Don't place scene changes in the last few frames of the video,
+        // these would basically be flashes
+        return false;
+    }
+
+    let back_over_threshold = back.iter().filter(|&&score| score >= threshold).count();
+    let forward_over_threshold = forward.iter().filter(|&&score| score >= threshold).count();
+
+    // Check for a scene change after a flash:
+    // some frames over the threshold behind us, none ahead.
+    // Pixel differences are prone to false flash detection,
+    // so more than one frame is required as evidence of a flash.
+    if forward_over_threshold == 0 && back_over_threshold >= 2 {
+        return true;
+    }
+
+    // Check for a scene change before a flash:
+    // only the furthest frame ahead is over the threshold,
+    // which is too far away to be part of the same flash.
+    if back_over_threshold == 0
+        && forward_over_threshold == 1
+        && forward.last().is_some_and(|&score| score >= threshold)
+    {
+        return true;
+    }
+
+    back_over_threshold == 0 && forward_over_threshold == 0
+}
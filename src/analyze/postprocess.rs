@@ -0,0 +1,240 @@
+//! Adjusting scene changes after detection,
+//! based on the pixel difference score of every frame.
+
+use std::collections::BTreeSet;
+
+use rav1e::prelude::{Frame, Pixel};
+
+use super::fast::FastScorer;
+use crate::{decoder::VideoDetails, DetectionOptions, FrameOverrides};
+
+/// Records the scores needed for post-processing while a clip is detected.
+pub struct PostProcessor<T: Pixel> {
+    scorer: FastScorer<T>,
+    /// The pixel difference score of each frame compared to the frame before it
+    scores: Vec<f64>,
+}
+
+impl<T: Pixel> PostProcessor<T> {
+    /// Returns `None` if `opts` does not enable any post-processing.
+    pub fn new(video_details: &VideoDetails, opts: DetectionOptions) -> Option<Self> {
+        let enabled = opts.min_scene_length.is_some()
+            || opts.max_scene_length.is_some()
+            || opts.target_scene_count.is_some();
+        enabled.then(|| Self {
+            scorer: FastScorer::new(video_details.width, video_details.height),
+            // The first frame has no previous frame to be compared to
+            scores: vec![0.0],
+        })
+    }
+
+    /// Scores the next frame, `current`, against the frame before it.
+    pub fn push(&mut self, previous: &Frame<T>, current: &Frame<T>) {
+        let frameno = self.scores.len();
+        self.scores
+            .push(self.scorer.score(frameno, previous, current));
+    }
+
+    pub fn finish(
+        &self,
+        scene_changes: &[usize],
+        opts: DetectionOptions,
+        overrides: &FrameOverrides,
+    ) -> Vec<usize> {
+        post_process(scene_changes, &self.scores, opts, overrides)
+    }
+}
+
+/// Applies the post-processing enabled in `opts` to `scene_changes`,
+/// keeping the forced cuts and avoiding the forbidden frames in `overrides`.
+///
+/// `scores` holds the pixel difference score of every frame in the clip.
+pub fn post_process(
+    scene_changes: &[usize],
+    scores: &[f64],
+    opts: DetectionOptions,
+    overrides: &FrameOverrides,
+) -> Vec<usize> {
+    let min_distance = opts
+        .min_scenecut_distance
+        .max(opts.min_scene_length)
+        .unwrap_or(1);
+    let mut scene_changes = opts.target_scene_count.map_or_else(
+        || scene_changes.to_vec(),
+        |target_scene_count| {
+            strongest_scene_changes(scores, target_scene_count, min_distance, overrides)
+        },
+    );
+    if let Some(min_scene_length) = opts.min_scene_length {
+        merge_short_scenes(&mut scene_changes, scores, min_scene_length, overrides);
+    }
+    if let Some(max_scene_length) = opts.max_scene_length {
+        // A scene can't be shorter than one frame
+        let max_scene_length = max_scene_length.max(1);
+        let window = opts
+            .scene_split_window
+            .unwrap_or(max_scene_length / 4)
+            .min(max_scene_length);
+        split_long_scenes(
+            &mut scene_changes,
+            scores,
+            max_scene_length,
+            window,
+            opts.min_scene_length.unwrap_or(1),
+            overrides,
+        );
+    }
+    scene_changes
+}
+
+/// Picks the frames with the highest scores as the scene changes
+/// that split the clip into `target_scene_count` scenes,
+/// skipping frames closer than `min_distance` to a scene change already picked
+/// or to the end of the clip.
+///
+/// The forced cuts in `overrides` are always picked and count towards the target,
+/// and forbidden frames are never picked.
+/// Fewer scenes are returned if the clip is too short to fit them all.
+fn strongest_scene_changes(
+    scores: &[f64],
+    target_scene_count: usize,
+    min_distance: usize,
+    overrides: &FrameOverrides,
+) -> Vec<usize> {
+    let frame_count = scores.len();
+    let mut frames = (1..frame_count)
+        .filter(|&frameno| !overrides.is_forbidden_cut(frameno))
+        .collect::<Vec<_>>();
+    // Stable, so earlier frames win ties
+    frames.sort_by(|&a, &b| scores[b].total_cmp(&scores[a]));
+
+    let mut scene_changes = BTreeSet::from([0]);
+    scene_changes.extend(overrides.forced_cuts.range(..frame_count));
+    for frameno in frames {
+        if scene_changes.len() >= target_scene_count {
+            break;
+        }
+        let previous = scene_changes.range(..frameno).next_back().copied();
+        let next = scene_changes
+            .range(frameno..)
+            .next()
+            .copied()
+            .unwrap_or(frame_count);
+        if previous.is_some_and(|previous| frameno - previous < min_distance)
+            || next - frameno < min_distance
+        {
+            continue;
+        }
+        scene_changes.insert(frameno);
+    }
+    scene_changes.into_iter().collect()
+}
+
+/// Merges scenes shorter than `min_scene_length` into a neighbouring scene,
+/// shortest scenes first.
+///
+/// Each short scene is merged by removing whichever of its two scene changes
+/// has the lower score, which joins it to the neighbour it is most similar to.
+/// Scenes between two forced cuts in `overrides` are left as they are.
+fn merge_short_scenes(
+    scene_changes: &mut Vec<usize>,
+    scores: &[f64],
+    min_scene_length: usize,
+    overrides: &FrameOverrides,
+) {
+    let frame_count = scores.len();
+    let score = |frameno: usize| scores.get(frameno).copied().unwrap_or_default();
+    loop {
+        // The first scene change is the start of the clip, which can't be removed
+        let removable = |i: usize| {
+            i > 0
+                && scene_changes
+                    .get(i)
+                    .is_some_and(|&frameno| !overrides.is_forced_cut(frameno))
+        };
+        let shortest = scene_changes
+            .iter()
+            .enumerate()
+            .filter_map(|(i, &start)| {
+                let end = scene_changes.get(i + 1).copied().unwrap_or(frame_count);
+                let length = end.saturating_sub(start);
+                if length >= min_scene_length {
+                    return None;
+                }
+                let remove = match (removable(i), removable(i + 1)) {
+                    (true, true) => {
+                        if score(start) <= score(end) {
+                            i
+                        } else {
+                            i + 1
+                        }
+                    }
+                    (true, false) => i,
+                    (false, true) => i + 1,
+                    // The whole clip is shorter than the minimum,
+                    // or the scene is between forced cuts
+                    (false, false) => return None,
+                };
+                Some((length, remove))
+            })
+            .min();
+        let Some((_, remove)) = shortest else {
+            return;
+        };
+        scene_changes.remove(remove);
+    }
+}
+
+/// Splits scenes longer than `max_scene_length` until none are left.
+///
+/// Each split is placed on the frame with the lowest score within `window`
+/// frames before the limit, which is usually a calmer moment than the limit
+/// itself. Frames that would leave a remainder shorter than
+/// `min_scene_length` are avoided if possible.
+///
+/// Frames forbidden by `overrides` are never split at. If the whole window is
+/// forbidden, the closest allowed frame before it is used, or after it
+/// if there is none, leaving a scene longer than `max_scene_length`.
+fn split_long_scenes(
+    scene_changes: &mut Vec<usize>,
+    scores: &[f64],
+    max_scene_length: usize,
+    window: usize,
+    min_scene_length: usize,
+    overrides: &FrameOverrides,
+) {
+    let frame_count = scores.len();
+    let mut split = Vec::with_capacity(scene_changes.len());
+    for (i, &start) in scene_changes.iter().enumerate() {
+        let end = scene_changes.get(i + 1).copied().unwrap_or(frame_count);
+        let mut scene_start = start;
+        split.push(scene_start);
+        while end - scene_start > max_scene_length {
+            let limit = scene_start + max_scene_length;
+            let first = (limit - window).max(scene_start + 1);
+            let last = limit.min(end.saturating_sub(min_scene_length));
+            let candidates = if first <= last {
+                first..=last
+            } else {
+                first..=limit
+            };
+            let after = *candidates.end() + 1;
+            // Prefer later frames on ties, to split as few times as possible
+            let best = candidates
+                .rev()
+                .filter(|&frameno| !overrides.is_forbidden_cut(frameno))
+                .min_by(|&a, &b| scores[a].total_cmp(&scores[b]));
+            let Some(next) = best.or_else(|| {
+                (scene_start + 1..first)
+                    .rev()
+                    .chain(after..end)
+                    .find(|&frameno| !overrides.is_forbidden_cut(frameno))
+            }) else {
+                break;
+            };
+            scene_start = next;
+            split.push(scene_start);
+        }
+    }
+    *scene_changes = split;
+}
@@ -0,0 +1,151 @@
+//! Choosing the frame that best represents each scene.
+
+use rav1e::prelude::{CastFromPrimitive, Frame, Pixel};
+
+use super::histogram::{histogram_shift, luma_histogram, BINS};
+use crate::decoder::VideoDetails;
+
+/// How to choose the frame that best represents a scene.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+pub enum RepresentativeFrame {
+    /// The frame whose luma histogram is closest to the mean histogram
+    /// of the scene, which avoids flashes and the ends of fades.
+    Histogram,
+    /// The frame with the most detail, which avoids motion blur.
+    Sharpness,
+}
+
+/// Records a measurement of each frame while a clip is detected,
+/// from which the representative frame of each scene is chosen
+/// once the scene changes are final.
+pub struct RepresentativeFrames {
+    video_details: VideoDetails,
+    measurements: Measurements,
+}
+
+enum Measurements {
+    Histograms(Vec<[u32; BINS]>),
+    Sharpness(Vec<f64>),
+}
+
+impl RepresentativeFrames {
+    pub fn new(video_details: VideoDetails, method: RepresentativeFrame) -> Self {
+        Self {
+            video_details,
+            measurements: match method {
+                RepresentativeFrame::Histogram => Measurements::Histograms(Vec::new()),
+                RepresentativeFrame::Sharpness => Measurements::Sharpness(Vec::new()),
+            },
+        }
+    }
+
+    /// Measures the next frame.
+    pub fn push<T: Pixel>(&mut self, frame: &Frame<T>) {
+        match &mut self.measurements {
+            Measurements::Histograms(histograms) => histograms.push(*luma_histogram(
+                frame,
+                histogram_shift(self.video_details.bit_depth),
+            )),
+            Measurements::Sharpness(sharpness_values) => {
+                sharpness_values.push(sharpness(frame, &self.video_details));
+            }
+        }
+    }
+
+    /// Returns the representative frame of each scene starting at `scene_changes`.
+    /// Ties go to the earliest frame.
+    pub fn finish(&self, scene_changes: &[usize]) -> Vec<usize> {
+        let frame_count = match &self.measurements {
+            Measurements::Histograms(histograms) => histograms.len(),
+            Measurements::Sharpness(sharpness_values) => sharpness_values.len(),
+        };
+        scene_changes
+            .iter()
+            .enumerate()
+            .map(|(i, &start)| {
+                let end = scene_changes.get(i + 1).copied().unwrap_or(frame_count);
+                match &self.measurements {
+                    Measurements::Histograms(histograms) => {
+                        closest_to_mean(&histograms[start..end]) + start
+                    }
+                    Measurements::Sharpness(sharpness_values) => {
+                        sharpest(&sharpness_values[start..end]) + start
+                    }
+                }
+            })
+            .collect()
+    }
+}
+
+/// The index of the histogram with the smallest difference
+/// to the mean of `histograms`.
+fn closest_to_mean(histograms: &[[u32; BINS]]) -> usize {
+    let mut mean = [0.0; BINS];
+    for histogram in histograms {
+        for (mean, &count) in mean.iter_mut().zip(histogram.iter()) {
+            *mean += f64::from(count);
+        }
+    }
+    let frames = histograms.len().max(1) as f64;
+    for mean in &mut mean {
+        *mean /= frames;
+    }
+
+    let distance = |histogram: &[u32; BINS]| -> f64 {
+        histogram
+            .iter()
+            .zip(mean.iter())
+            .map(|(&count, &mean)| (f64::from(count) - mean).abs())
+            .sum()
+    };
+    histograms
+        .iter()
+        .map(distance)
+        .enumerate()
+        .min_by(|(_, a), (_, b)| a.total_cmp(b))
+        .map_or(0, |(i, _)| i)
+}
+
+/// The index of the highest of `sharpness_values`.
+fn sharpest(sharpness_values: &[f64]) -> usize {
+    sharpness_values
+        .iter()
+        .enumerate()
+        // Reversed, since `max_by` returns the last of equal elements
+        .rev()
+        .max_by(|(_, a), (_, b)| a.total_cmp(b))
+        .map_or(0, |(i, _)| i)
+}
+
+/// The variance of the Laplacian of the luma plane,
+/// in 8-bit units so it does not depend on the bit depth.
+///
+/// Higher values mean more detail and sharper edges.
+pub fn sharpness<T: Pixel>(frame: &Frame<T>, video_details: &VideoDetails) -> f64 {
+    let (width, height) = (video_details.width, video_details.height);
+    if width < 3 || height < 3 {
+        return 0.0;
+    }
+    let luma = &frame.planes[0];
+    let mut sum = 0i64;
+    let mut sum_squares = 0i64;
+    for y in 1..height - 1 {
+        let above = luma.row(y as isize - 1);
+        let row = luma.row(y as isize);
+        let below = luma.row(y as isize + 1);
+        for x in 1..width - 1 {
+            let laplacian = 4 * i64::from(u32::cast_from(row[x]))
+                - i64::from(u32::cast_from(row[x - 1]))
+                - i64::from(u32::cast_from(row[x + 1]))
+                - i64::from(u32::cast_from(above[x]))
+                - i64::from(u32::cast_from(below[x]));
+            sum += laplacian;
+            sum_squares += laplacian * laplacian;
+        }
+    }
+    let count = ((width - 2) * (height - 2)) as f64;
+    let mean = sum as f64 / count;
+    let variance = mean.mul_add(-mean, sum_squares as f64 / count);
+    variance / f64::from(1u32 << (2 * (video_details.bit_depth - 8)))
+}
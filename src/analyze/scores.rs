@@ -0,0 +1,265 @@
+use std::io::{Read, Write};
+
+use anyhow::{bail, ensure};
+use rav1e::prelude::{Frame, Pixel};
+
+use super::{
+    fast::FastScorer, histogram::HistogramScorer, hsv::HsvScorer, postprocess::post_process,
+    Algorithm, Decider, Scores,
+};
+use crate::{decoder::VideoDetails, DetectionOptions, FrameOverrides, HsvWeights};
+
+/// The scores of every native algorithm for each frame of a clip,
+/// computed in a single decoding pass.
+///
+/// Deciding which frames are scene changes from these scores is cheap,
+/// so they can be replayed with many different `DetectionOptions`.
+#[derive(Debug, Clone)]
+pub struct FrameScores {
+    /// The bit depth of the clip, which pixel difference thresholds depend on.
+    pub bit_depth: usize,
+    /// The weights `hsv` was scored with.
+    pub hsv_weights: HsvWeights,
+    /// The score of each frame compared to the frame before it,
+    /// for `Algorithm::PixelDifference` and `Algorithm::Adaptive`.
+    /// The first frame has no previous frame and is scored as 0.
+    pub pixel_difference: Vec<f64>,
+    /// The score of each frame for `Algorithm::Histogram`.
+    pub histogram: Vec<f64>,
+    /// The score of each frame for `Algorithm::Hsv`.
+    pub hsv: Vec<f64>,
+}
+
+impl FrameScores {
+    /// The number of frames scored.
+    #[must_use]
+    pub fn frame_count(&self) -> usize {
+        self.pixel_difference.len()
+    }
+
+    /// Decides which frames are scene changes, with the same results
+    /// as running [`crate::detect_scene_changes`] on the clip
+    /// with `SceneDetectionSpeed::Fast` and `opts`.
+    ///
+    /// `opts.analysis_speed`, `opts.ensemble` and `opts.hsv_weights`
+    /// are ignored.
+    #[must_use]
+    pub fn scene_changes(&self, opts: DetectionOptions) -> Vec<usize> {
+        self.scene_changes_with_overrides(opts, &FrameOverrides::default())
+    }
+
+    /// Decides which frames are scene changes, with the same results
+    /// as running [`crate::detect_scene_changes_with_overrides`] on the clip
+    /// with `SceneDetectionSpeed::Fast`, `opts` and `overrides`.
+    ///
+    /// The speed of each zone in `overrides` is ignored, like `opts.analysis_speed`.
+    #[must_use]
+    pub fn scene_changes_with_overrides(
+        &self,
+        opts: DetectionOptions,
+        overrides: &FrameOverrides,
+    ) -> Vec<usize> {
+        let mut zone = overrides.zone_at(0);
+        let mut zone_start = 0;
+        let mut zone_opts = overrides.options_in_zone(opts, zone);
+        let mut decider = Decider::new(self.bit_depth, zone_opts);
+
+        let mut scene_changes = vec![0];
+        for frameno in 1..self.frame_count() {
+            if overrides.zone_at(frameno) != zone {
+                zone = overrides.zone_at(frameno);
+                zone_start = frameno;
+                zone_opts = overrides.options_in_zone(opts, zone);
+                decider = Decider::new(self.bit_depth, zone_opts);
+            }
+            // Only use the scores the streaming detector would have read by now
+            let visible = Lookahead {
+                scores: self.scores(zone_opts.algorithm),
+                start: zone_start,
+                end: frameno.saturating_add(opts.lookahead_distance),
+            };
+            let previous_keyframe = scene_changes.last().copied().unwrap_or_default();
+            let detected = decider.is_scenecut(&visible, frameno, previous_keyframe);
+            if overrides.is_forced_cut(frameno)
+                || (detected && !overrides.is_forbidden_cut(frameno))
+            {
+                scene_changes.push(frameno);
+            }
+        }
+        post_process(&scene_changes, &self.pixel_difference, opts, overrides)
+    }
+
+    /// The scores used by `algorithm`.
+    fn scores(&self, algorithm: Algorithm) -> &[f64] {
+        match algorithm {
+            Algorithm::PixelDifference | Algorithm::Adaptive => &self.pixel_difference,
+            Algorithm::Histogram => &self.histogram,
+            Algorithm::Hsv => &self.hsv,
+        }
+    }
+}
+
+/// Identifies files written by [`FrameScores::write_to`].
+const MAGIC: &[u8; 8] = b"AVSCSCOR";
+const VERSION: u32 = 1;
+
+impl FrameScores {
+    /// Writes the scores in a compact binary format,
+    /// which can be read back with [`FrameScores::read_from`].
+    ///
+    /// Writes are not buffered, so `writer` should usually be a `BufWriter`.
+    ///
+    /// # Errors
+    ///
+    /// - If writing fails.
+    pub fn write_to<W: Write>(&self, mut writer: W) -> anyhow::Result<()> {
+        writer.write_all(MAGIC)?;
+        writer.write_all(&VERSION.to_le_bytes())?;
+        writer.write_all(&(self.bit_depth as u32).to_le_bytes())?;
+        for weight in [
+            self.hsv_weights.hue,
+            self.hsv_weights.saturation,
+            self.hsv_weights.value,
+        ] {
+            writer.write_all(&weight.to_le_bytes())?;
+        }
+        writer.write_all(&(self.frame_count() as u64).to_le_bytes())?;
+        for scores in [&self.pixel_difference, &self.histogram, &self.hsv] {
+            for score in scores {
+                writer.write_all(&score.to_le_bytes())?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Reads scores written by [`FrameScores::write_to`].
+    ///
+    /// # Errors
+    ///
+    /// - If reading fails or the data ends early.
+    /// - If the data was not written by [`FrameScores::write_to`],
+    ///   or by an incompatible version of this crate.
+    pub fn read_from<R: Read>(mut reader: R) -> anyhow::Result<Self> {
+        let mut magic = [0; 8];
+        reader.read_exact(&mut magic)?;
+        ensure!(&magic == MAGIC, "Not a frame scores file");
+        let version = read_u32(&mut reader)?;
+        if version != VERSION {
+            bail!("Unsupported frame scores version {version}, expected {VERSION}");
+        }
+        let bit_depth = read_u32(&mut reader)? as usize;
+        ensure!(
+            (8..=16).contains(&bit_depth),
+            "Invalid bit depth {bit_depth} in frame scores"
+        );
+        let hsv_weights = HsvWeights {
+            hue: read_f64(&mut reader)?,
+            saturation: read_f64(&mut reader)?,
+            value: read_f64(&mut reader)?,
+        };
+
+        let mut frame_count = [0; 8];
+        reader.read_exact(&mut frame_count)?;
+        let frame_count = usize::try_from(u64::from_le_bytes(frame_count))?;
+        let mut read_scores = || -> anyhow::Result<Vec<f64>> {
+            // Don't trust the frame count for the allocation size,
+            // a corrupt file should fail to read rather than exhaust memory
+            let mut scores = Vec::with_capacity(frame_count.min(1 << 20));
+            for _ in 0..frame_count {
+                scores.push(read_f64(&mut reader)?);
+            }
+            Ok(scores)
+        };
+        Ok(FrameScores {
+            bit_depth,
+            hsv_weights,
+            pixel_difference: read_scores()?,
+            histogram: read_scores()?,
+            hsv: read_scores()?,
+        })
+    }
+}
+
+fn read_u32<R: Read>(reader: &mut R) -> anyhow::Result<u32> {
+    let mut bytes = [0; 4];
+    reader.read_exact(&mut bytes)?;
+    Ok(u32::from_le_bytes(bytes))
+}
+
+fn read_f64<R: Read>(reader: &mut R) -> anyhow::Result<f64> {
+    let mut bytes = [0; 8];
+    reader.read_exact(&mut bytes)?;
+    Ok(f64::from_le_bytes(bytes))
+}
+
+/// The scores of a whole clip, from frame `start` up to and including frame `end`.
+struct Lookahead<'a> {
+    scores: &'a [f64],
+    start: usize,
+    end: usize,
+}
+
+impl Scores for Lookahead<'_> {
+    fn score(&self, frameno: usize) -> Option<f64> {
+        if frameno == 0 || frameno < self.start || frameno > self.end {
+            return None;
+        }
+        self.scores.get(frameno).copied()
+    }
+}
+
+/// Scores frames with every native algorithm.
+pub struct FrameScorer<T: Pixel> {
+    pixel_difference: FastScorer<T>,
+    histogram: HistogramScorer,
+    hsv: HsvScorer,
+    scores: FrameScores,
+}
+
+impl<T: Pixel> FrameScorer<T> {
+    pub fn new(video_details: &VideoDetails, hsv_weights: HsvWeights) -> Self {
+        Self {
+            pixel_difference: FastScorer::new(video_details.width, video_details.height),
+            histogram: HistogramScorer::new(video_details.bit_depth),
+            hsv: HsvScorer::new(
+                video_details.width,
+                video_details.height,
+                video_details.bit_depth,
+                video_details.chroma_sampling,
+                hsv_weights,
+            ),
+            scores: FrameScores {
+                bit_depth: video_details.bit_depth,
+                hsv_weights,
+                pixel_difference: Vec::new(),
+                histogram: Vec::new(),
+                hsv: Vec::new(),
+            },
+        }
+    }
+
+    /// Scores the next frame, `current`, against the frame before it.
+    /// `previous` is `None` for the first frame.
+    pub fn push(&mut self, previous: Option<&Frame<T>>, current: &Frame<T>) {
+        let Some(previous) = previous else {
+            self.scores.pixel_difference.push(0.0);
+            self.scores.histogram.push(0.0);
+            self.scores.hsv.push(0.0);
+            return;
+        };
+        let frameno = self.scores.frame_count();
+        self.scores
+            .pixel_difference
+            .push(self.pixel_difference.score(frameno, previous, current));
+        self.scores
+            .histogram
+            .push(self.histogram.score(frameno, previous, current));
+        self.scores
+            .hsv
+            .push(self.hsv.score(frameno, previous, current));
+    }
+
+    pub fn finish(self) -> FrameScores {
+        self.scores
+    }
+}
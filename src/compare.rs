@@ -0,0 +1,125 @@
+//! Finding the scenes two clips have in common, from their perceptual hashes.
+
+use anyhow::anyhow;
+
+use crate::DetectionResults;
+
+/// A scene in one clip that matched a scene in the other.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize))]
+pub struct SceneMatch {
+    /// The index of the scene in the first clip.
+    pub left_scene: usize,
+    /// The index of the scene in the second clip.
+    pub right_scene: usize,
+    /// The frame number the scene starts at in the first clip.
+    pub left_frame: usize,
+    /// The frame number the scene starts at in the second clip.
+    pub right_frame: usize,
+    /// The number of bits that differ between the hashes of the scenes.
+    pub distance: u32,
+}
+
+/// The result of comparing the scenes of two clips.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize))]
+pub struct Comparison {
+    /// Scenes found in both clips, in order.
+    pub matches: Vec<SceneMatch>,
+    /// The fraction of scenes in the first clip that are in the second.
+    pub left_coverage: f64,
+    /// The fraction of scenes in the second clip that are in the first.
+    pub right_coverage: f64,
+}
+
+/// Aligns the scenes of two clips, detected with `DetectionOptions::scene_hashes`.
+///
+/// Scenes match if their hashes differ by at most `max_distance` bits.
+/// Matches keep the order of both clips, so a scene that only appears once
+/// in one clip is only matched once.
+///
+/// # Errors
+///
+/// - If either of the results has no scene hashes.
+pub fn compare(
+    left: &DetectionResults,
+    right: &DetectionResults,
+    max_distance: u32,
+) -> anyhow::Result<Comparison> {
+    let hashes = |results: &DetectionResults| {
+        results
+            .scene_hashes
+            .clone()
+            .ok_or_else(|| anyhow!("Scenes must be detected with scene hashes to be compared"))
+    };
+    let (left_hashes, right_hashes) = (hashes(left)?, hashes(right)?);
+    let matches = align(&left_hashes, &right_hashes, max_distance)
+        .into_iter()
+        .map(|(left_scene, right_scene)| SceneMatch {
+            left_scene,
+            right_scene,
+            left_frame: left.scene_changes[left_scene],
+            right_frame: right.scene_changes[right_scene],
+            distance: (left_hashes[left_scene] ^ right_hashes[right_scene]).count_ones(),
+        })
+        .collect::<Vec<_>>();
+    let coverage = |scenes: usize| {
+        if scenes == 0 {
+            0.0
+        } else {
+            matches.len() as f64 / scenes as f64
+        }
+    };
+    Ok(Comparison {
+        left_coverage: coverage(left_hashes.len()),
+        right_coverage: coverage(right_hashes.len()),
+        matches,
+    })
+}
+
+/// Returns the pairs of indices of the longest sequence of hashes
+/// that match in both `left` and `right`, preferring closer matches on ties.
+#[must_use]
+pub fn align(left: &[u64], right: &[u64], max_distance: u32) -> Vec<(usize, usize)> {
+    let distance = |i: usize, j: usize| (left[i] ^ right[j]).count_ones();
+    let columns = right.len() + 1;
+    // The most matches, then the lowest total distance, of each pair of prefixes
+    let mut best = vec![(0u32, 0u32); (left.len() + 1) * columns];
+    let better = |a: (u32, u32), b: (u32, u32)| a.0 > b.0 || (a.0 == b.0 && a.1 < b.1);
+    for i in 1..=left.len() {
+        for j in 1..=right.len() {
+            let mut cell = best[(i - 1) * columns + j];
+            let skip_right = best[i * columns + j - 1];
+            if better(skip_right, cell) {
+                cell = skip_right;
+            }
+            let distance = distance(i - 1, j - 1);
+            if distance <= max_distance {
+                let (matches, total) = best[(i - 1) * columns + j - 1];
+                let matched = (matches + 1, total + distance);
+                if better(matched, cell) {
+                    cell = matched;
+                }
+            }
+            best[i * columns + j] = cell;
+        }
+    }
+
+    // Walk back through the table to find which pairs were matched
+    let mut pairs = Vec::new();
+    let (mut i, mut j) = (left.len(), right.len());
+    while i > 0 && j > 0 {
+        let cell = best[i * columns + j];
+        if cell == best[(i - 1) * columns + j] {
+            i -= 1;
+        } else if cell == best[i * columns + j - 1] {
+            j -= 1;
+        } else {
+            pairs.push((i - 1, j - 1));
+            i -= 1;
+            j -= 1;
+        }
+    }
+    pairs.reverse();
+    pairs
+}
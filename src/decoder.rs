@@ -29,13 +29,15 @@ impl<R: Read> Decoder<R> {
         }
     }
 
+    /// Reads the next frame, or `None` at the end of the input.
+    ///
     /// # Errors
     ///
     /// - If a frame cannot be read.
     pub fn read_video_frame<T: Pixel>(
         &mut self,
         video_details: &VideoDetails,
-    ) -> anyhow::Result<Frame<T>> {
+    ) -> anyhow::Result<Option<Frame<T>>> {
         match self {
             Decoder::Y4m(dec) => crate::y4m::read_video_frame::<R, T>(dec, video_details),
             #[cfg(feature = "vapoursynth")]
@@ -44,6 +46,20 @@ impl<R: Read> Decoder<R> {
             Decoder::Ffmpeg(dec) => dec.read_video_frame::<T>(),
         }
     }
+
+    /// The presentation time of each frame read so far, in seconds
+    /// from the first frame, if the decoder reports them.
+    ///
+    /// Only the ffmpeg decoder reports timestamps, which are accurate
+    /// for variable frame rate video.
+    #[must_use]
+    pub fn timestamps(&self) -> Option<&[f64]> {
+        match self {
+            #[cfg(feature = "ffmpeg")]
+            Decoder::Ffmpeg(dec) => Some(dec.timestamps()),
+            _ => None,
+        }
+    }
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -54,6 +70,9 @@ pub struct VideoDetails {
     pub chroma_sampling: ChromaSampling,
     pub chroma_sample_position: ChromaSamplePosition,
     pub time_base: Rational,
+    /// The name of the source's pixel format, if its frames are converted
+    /// to the layout described by `bit_depth` and `chroma_sampling`
+    pub converted_from: Option<&'static str>,
 }
 
 impl Default for VideoDetails {
@@ -65,6 +84,7 @@ impl Default for VideoDetails {
             chroma_sampling: ChromaSampling::Cs420,
             chroma_sample_position: ChromaSamplePosition::Unknown,
             time_base: Rational { num: 30, den: 1 },
+            converted_from: None,
         }
     }
 }
@@ -0,0 +1,151 @@
+//! Reading and writing CMX3600 edit decision lists (EDLs).
+
+use std::fmt::Write;
+
+use anyhow::{bail, ensure, Context};
+
+/// Returns the frame numbers where each video event in `edl` starts,
+/// relative to the start of the timeline and in ascending order.
+///
+/// `fps` is the frame rate of the timeline, used to convert timecodes
+/// to frame numbers. Drop-frame timecodes (using `;` as the last separator)
+/// are supported for NTSC frame rates.
+///
+/// # Errors
+///
+/// - If an event contains an invalid timecode.
+/// - If `edl` contains no video events.
+pub fn parse(edl: &str, fps: f64) -> anyhow::Result<Vec<usize>> {
+    let mut record_ins = Vec::new();
+    for (line_number, line) in edl.lines().enumerate() {
+        let fields = line.split_whitespace().collect::<Vec<_>>();
+        // Event lines start with the event number,
+        // followed by the reel, the track type and the transition
+        let is_event = fields
+            .first()
+            .is_some_and(|field| field.bytes().all(|b| b.is_ascii_digit()));
+        if !is_event || fields.len() < 8 {
+            continue;
+        }
+        if !fields[2].contains('V') && fields[2] != "B" {
+            // Audio-only event
+            continue;
+        }
+
+        // The record in point is the third of the four timecodes at the end of the line
+        let record_in = fields[fields.len() - 2];
+        record_ins.push(
+            parse_timecode(record_in, fps)
+                .with_context(|| format!("Invalid event on line {}", line_number + 1))?,
+        );
+    }
+    ensure!(!record_ins.is_empty(), "No video events found");
+
+    record_ins.sort_unstable();
+    record_ins.dedup();
+    let start = record_ins[0];
+    Ok(record_ins.into_iter().map(|frame| frame - start).collect())
+}
+
+/// Converts a `HH:MM:SS:FF` timecode to a frame number.
+///
+/// # Errors
+///
+/// - If the timecode is not in the expected format.
+pub fn parse_timecode(timecode: &str, fps: f64) -> anyhow::Result<usize> {
+    let parts = timecode
+        .split([':', ';', '.'])
+        .map(str::parse::<usize>)
+        .collect::<Result<Vec<_>, _>>()
+        .with_context(|| format!("Invalid timecode {timecode}"))?;
+    let &[hours, minutes, seconds, frames] = parts.as_slice() else {
+        bail!("Invalid timecode {timecode}");
+    };
+
+    let timebase = fps.round() as usize;
+    let total_minutes = hours * 60 + minutes;
+    let mut frame = (total_minutes * 60 + seconds) * timebase + frames;
+    if timecode.contains(';') {
+        // Drop-frame timecodes skip frame numbers at the start of every minute,
+        // except every tenth minute
+        let dropped_per_minute = timebase / 15;
+        frame -= dropped_per_minute * (total_minutes - total_minutes / 10);
+    }
+    Ok(frame)
+}
+
+/// Writes the scenes starting at `scene_changes` as the video events of an EDL,
+/// so the cuts can be imported into an NLE.
+///
+/// Each scene becomes a cut from the same source, named `clip_name` if given,
+/// with matching source and record timecodes. NTSC frame rates such as
+/// 29.97 use drop-frame timecodes.
+#[must_use]
+pub fn write(
+    title: &str,
+    scene_changes: &[usize],
+    frame_count: usize,
+    fps: f64,
+    clip_name: Option<&str>,
+) -> String {
+    let drop_frame = is_drop_frame(fps);
+    let mut edl = format!("TITLE: {title}\n");
+    edl.push_str(if drop_frame {
+        "FCM: DROP FRAME\n"
+    } else {
+        "FCM: NON-DROP FRAME\n"
+    });
+    for (i, &start) in scene_changes.iter().enumerate() {
+        let end = scene_changes.get(i + 1).copied().unwrap_or(frame_count);
+        let (start, end) = (
+            format_timecode(start, fps, drop_frame),
+            format_timecode(end, fps, drop_frame),
+        );
+        let _ = writeln!(
+            edl,
+            "\n{:03}  AX       V     C        {start} {end} {start} {end}",
+            i + 1
+        );
+        if let Some(clip_name) = clip_name {
+            let _ = writeln!(edl, "* FROM CLIP NAME: {clip_name}");
+        }
+    }
+    edl
+}
+
+/// Converts a frame number to a `HH:MM:SS:FF` timecode,
+/// or a `HH:MM:SS;FF` drop-frame timecode if `drop_frame` is set.
+#[must_use]
+pub fn format_timecode(frame: usize, fps: f64, drop_frame: bool) -> String {
+    let timebase = (fps.round() as usize).max(1);
+    let mut frame = frame;
+    if drop_frame {
+        // Skip the frame numbers `parse_timecode` removes
+        let dropped_per_minute = timebase / 15;
+        let frames_per_minute = timebase * 60 - dropped_per_minute;
+        let frames_per_ten_minutes = frames_per_minute * 10 + dropped_per_minute;
+        let tens = frame / frames_per_ten_minutes;
+        let remainder = frame % frames_per_ten_minutes;
+        frame += dropped_per_minute * 9 * tens;
+        if remainder > dropped_per_minute {
+            frame += dropped_per_minute * ((remainder - dropped_per_minute) / frames_per_minute);
+        }
+    }
+
+    let frames = frame % timebase;
+    let seconds = frame / timebase;
+    let separator = if drop_frame { ';' } else { ':' };
+    format!(
+        "{:02}:{:02}:{:02}{separator}{frames:02}",
+        seconds / 3600,
+        seconds / 60 % 60,
+        seconds % 60
+    )
+}
+
+/// Whether timecodes at `fps` are conventionally drop-frame,
+/// as for 29.97 and 59.94 fps.
+fn is_drop_frame(fps: f64) -> bool {
+    let timebase = fps.round();
+    (fps - timebase).abs() > 1e-3 && (timebase as usize).is_multiple_of(30)
+}
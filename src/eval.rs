@@ -0,0 +1,308 @@
+//! Evaluating detected scene changes against a ground truth.
+
+use std::{fs, ops::Range, path::Path};
+
+use anyhow::{bail, Context};
+
+use crate::edl;
+
+/// A detected scene change that was matched to one in the ground truth.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize))]
+pub struct MatchedCut {
+    /// The frame number in the ground truth.
+    pub expected: usize,
+    /// The frame number that was detected.
+    pub detected: usize,
+    /// How many frames after the expected frame the detected frame is.
+    pub offset: isize,
+}
+
+/// The result of comparing detected scene changes to a ground truth.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize))]
+pub struct Evaluation {
+    /// The fraction of detected scene changes that are in the ground truth.
+    pub precision: f64,
+    /// The fraction of scene changes in the ground truth that were detected.
+    pub recall: f64,
+    /// The harmonic mean of `precision` and `recall`.
+    pub f1: f64,
+    /// Detected scene changes that matched the ground truth.
+    pub matched: Vec<MatchedCut>,
+    /// Scene changes in the ground truth that were not detected.
+    pub missed: Vec<usize>,
+    /// Detected scene changes that are not in the ground truth.
+    pub spurious: Vec<usize>,
+}
+
+/// Matches `detected` scene changes to those in `ground_truth`
+/// that are at most `tolerance` frames away.
+///
+/// Each scene change is matched at most once, preferring the closest pairs.
+/// Frame 0 is ignored in both lists, since it always starts a scene.
+#[must_use]
+pub fn evaluate(detected: &[usize], ground_truth: &[usize], tolerance: usize) -> Evaluation {
+    let mut detected = detected
+        .iter()
+        .copied()
+        .filter(|&frame| frame > 0)
+        .collect::<Vec<_>>();
+    detected.sort_unstable();
+    detected.dedup();
+    let mut expected = ground_truth
+        .iter()
+        .copied()
+        .filter(|&frame| frame > 0)
+        .collect::<Vec<_>>();
+    expected.sort_unstable();
+    expected.dedup();
+
+    // Every pair within the tolerance, as indices into `expected` and `detected`
+    let mut candidates = Vec::new();
+    let mut first = 0;
+    for (i, &expected_frame) in expected.iter().enumerate() {
+        while first < detected.len() && detected[first] + tolerance < expected_frame {
+            first += 1;
+        }
+        for (j, &detected_frame) in detected.iter().enumerate().skip(first) {
+            if detected_frame > expected_frame + tolerance {
+                break;
+            }
+            candidates.push((expected_frame.abs_diff(detected_frame), i, j));
+        }
+    }
+    candidates.sort_unstable();
+
+    let mut expected_matched = vec![false; expected.len()];
+    let mut detected_matched = vec![false; detected.len()];
+    let mut matched = Vec::new();
+    for (_, i, j) in candidates {
+        if expected_matched[i] || detected_matched[j] {
+            continue;
+        }
+        expected_matched[i] = true;
+        detected_matched[j] = true;
+        matched.push(MatchedCut {
+            expected: expected[i],
+            detected: detected[j],
+            offset: detected[j] as isize - expected[i] as isize,
+        });
+    }
+    matched.sort_unstable_by_key(|cut| cut.expected);
+
+    let missed = unmatched(&expected, &expected_matched);
+    let spurious = unmatched(&detected, &detected_matched);
+    let precision = ratio(matched.len(), detected.len());
+    let recall = ratio(matched.len(), expected.len());
+    let f1 = if precision + recall > 0.0 {
+        2.0 * precision * recall / (precision + recall)
+    } else {
+        0.0
+    };
+
+    Evaluation {
+        precision,
+        recall,
+        f1,
+        matched,
+        missed,
+        spurious,
+    }
+}
+
+fn unmatched(frames: &[usize], matched: &[bool]) -> Vec<usize> {
+    frames
+        .iter()
+        .zip(matched.iter())
+        .filter(|(_, &matched)| !matched)
+        .map(|(&frame, _)| frame)
+        .collect()
+}
+
+/// A ratio that is perfect when there is nothing to get wrong.
+fn ratio(count: usize, total: usize) -> f64 {
+    if total == 0 {
+        1.0
+    } else {
+        count as f64 / total as f64
+    }
+}
+
+/// Reads a list of scene changes from a file, based on its extension:
+///
+/// - `.json`: Either the output of this crate's binary or an array of frame
+///   numbers. Requires the `serialize` feature.
+/// - `.csv`: One frame number per line, taken from a column named `frame` or
+///   `start frame` if there is a header, or from the first column otherwise.
+/// - `.edl`: A CMX3600 EDL, using the start of each event. Requires `fps`.
+/// - `.otio`: An `OpenTimelineIO` timeline, using the start of each clip on the
+///   first video track. Requires the `serialize` feature.
+///
+/// # Errors
+///
+/// - If the file cannot be read or is not in the expected format.
+pub fn read_cut_list<P: AsRef<Path>>(path: P, fps: Option<f64>) -> anyhow::Result<Vec<usize>> {
+    let path = path.as_ref();
+    let contents =
+        fs::read_to_string(path).with_context(|| format!("Could not read {}", path.display()))?;
+    let extension = path
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .unwrap_or_default();
+
+    if extension.eq_ignore_ascii_case("json") {
+        #[cfg(feature = "serialize")]
+        return parse_json_cuts(&contents);
+        #[cfg(not(feature = "serialize"))]
+        bail!("Reading JSON requires the `serialize` feature");
+    }
+    if extension.eq_ignore_ascii_case("csv") {
+        return parse_csv_cuts(&contents);
+    }
+    if extension.eq_ignore_ascii_case("edl") {
+        let fps = fps.context("Reading an EDL requires a frame rate")?;
+        return edl::parse(&contents, fps);
+    }
+    if extension.eq_ignore_ascii_case("otio") {
+        #[cfg(feature = "serialize")]
+        return crate::otio::parse(&contents, fps);
+        #[cfg(not(feature = "serialize"))]
+        bail!("Reading OTIO requires the `serialize` feature");
+    }
+    bail!("Unknown cut list format {}", path.display())
+}
+
+/// Reads a list of frame ranges from a file, such as
+/// `FrameOverrides::forbidden_ranges`, based on its extension:
+///
+/// - `.json`: An array of `[start, end]` pairs or `{"start", "end"}` objects.
+///   Requires the `serialize` feature.
+/// - `.csv`: One range per line, with the start in the first column
+///   and the end in the second, unless there is a header naming
+///   `start` and `end` columns.
+///
+/// The end of each range is exclusive.
+///
+/// # Errors
+///
+/// - If the file cannot be read or is not in the expected format.
+pub fn read_frame_ranges<P: AsRef<Path>>(path: P) -> anyhow::Result<Vec<Range<usize>>> {
+    let path = path.as_ref();
+    let contents =
+        fs::read_to_string(path).with_context(|| format!("Could not read {}", path.display()))?;
+    let extension = path
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .unwrap_or_default();
+
+    if extension.eq_ignore_ascii_case("json") {
+        #[cfg(feature = "serialize")]
+        return parse_json_ranges(&contents);
+        #[cfg(not(feature = "serialize"))]
+        bail!("Reading JSON requires the `serialize` feature");
+    }
+    if extension.eq_ignore_ascii_case("csv") {
+        return parse_csv_ranges(&contents);
+    }
+    bail!("Unknown frame range format {}", path.display())
+}
+
+#[cfg(feature = "serialize")]
+fn parse_json_cuts(contents: &str) -> anyhow::Result<Vec<usize>> {
+    #[derive(serde::Deserialize)]
+    #[serde(untagged)]
+    enum CutList {
+        Frames(Vec<usize>),
+        Results { scene_changes: Vec<usize> },
+    }
+
+    Ok(match serde_json::from_str(contents)? {
+        CutList::Frames(frames)
+        | CutList::Results {
+            scene_changes: frames,
+        } => frames,
+    })
+}
+
+fn parse_csv_cuts(contents: &str) -> anyhow::Result<Vec<usize>> {
+    let mut column = 0;
+    let mut frames = Vec::new();
+    let mut first_line = true;
+    for (line_number, line) in contents.lines().enumerate() {
+        let fields = line.split(',').map(str::trim).collect::<Vec<_>>();
+        if fields.iter().all(|field| field.is_empty()) {
+            continue;
+        }
+        // Only the first line may be a header, any other line that
+        // isn't a number is an error
+        let may_be_header = first_line;
+        first_line = false;
+        if may_be_header && fields[0].parse::<usize>().is_err() {
+            if let Some(index) = fields.iter().position(|field| {
+                field.eq_ignore_ascii_case("frame") || field.eq_ignore_ascii_case("start frame")
+            }) {
+                column = index;
+            }
+            continue;
+        }
+        let frame = fields
+            .get(column)
+            .and_then(|field| field.parse().ok())
+            .with_context(|| format!("Invalid frame number on line {}", line_number + 1))?;
+        frames.push(frame);
+    }
+    Ok(frames)
+}
+
+#[cfg(feature = "serialize")]
+fn parse_json_ranges(contents: &str) -> anyhow::Result<Vec<Range<usize>>> {
+    #[derive(serde::Deserialize)]
+    #[serde(untagged)]
+    enum FrameRange {
+        Pair(usize, usize),
+        Range { start: usize, end: usize },
+    }
+
+    let ranges: Vec<FrameRange> = serde_json::from_str(contents)?;
+    Ok(ranges
+        .into_iter()
+        .map(|range| match range {
+            FrameRange::Pair(start, end) | FrameRange::Range { start, end } => start..end,
+        })
+        .collect())
+}
+
+fn parse_csv_ranges(contents: &str) -> anyhow::Result<Vec<Range<usize>>> {
+    let mut columns = (0, 1);
+    let mut ranges = Vec::new();
+    let mut first_line = true;
+    for (line_number, line) in contents.lines().enumerate() {
+        let fields = line.split(',').map(str::trim).collect::<Vec<_>>();
+        if fields.iter().all(|field| field.is_empty()) {
+            continue;
+        }
+        let may_be_header = first_line;
+        first_line = false;
+        if may_be_header && fields[0].parse::<usize>().is_err() {
+            // A header line, as in `parse_csv_cuts`
+            let column = |name: &str| {
+                fields
+                    .iter()
+                    .position(|field| field.eq_ignore_ascii_case(name))
+            };
+            if let (Some(start), Some(end)) = (column("start"), column("end")) {
+                columns = (start, end);
+            }
+            continue;
+        }
+        let frame = |column: usize| {
+            fields
+                .get(column)
+                .and_then(|field| field.parse::<usize>().ok())
+                .with_context(|| format!("Invalid frame range on line {}", line_number + 1))
+        };
+        ranges.push(frame(columns.0)?..frame(columns.1)?);
+    }
+    Ok(ranges)
+}
@@ -3,28 +3,65 @@ extern crate ffmpeg_the_third as ffmpeg;
 use std::path::Path;
 
 use anyhow::bail;
-use ffmpeg::{format, frame};
-use ffmpeg::codec::{decoder, packet};
+use ffmpeg::codec::decoder;
 use ffmpeg::format::context;
 use ffmpeg::media::Type;
+use ffmpeg::software::scaling;
+use ffmpeg::{format, frame};
 use ffmpeg_the_third::threading;
-use rav1e::{Frame, Pixel};
 use rav1e::color::{ChromaSamplePosition, ChromaSampling};
 use rav1e::data::Rational;
+use rav1e::{Frame, Pixel};
 
 use crate::decoder::VideoDetails;
 
-/// An interface that is used for decoding a video stream using ffmpeg
+/// Options for opening a file with [`FfmpegDecoder`]
 ///
-/// There have been desync issue reported with this decoder
-/// on some video files. Use at your own risk!
+/// By default, the stream ffmpeg considers the best video stream is decoded.
+#[derive(Debug, Clone)]
+pub struct FfmpegOptions {
+    /// Decode the stream with this index among all streams of the file,
+    /// as listed by `ffprobe`. Takes precedence over the other options.
+    pub stream_index: Option<usize>,
+    /// Only decode a video stream whose `language` tag is this, e.g. `eng`
+    pub language: Option<String>,
+    /// Only decode a video stream whose `title` tag contains this,
+    /// ignoring case
+    pub title: Option<String>,
+    /// Skip attached pictures, such as cover art, which are video streams
+    /// of a single image
+    pub skip_attached_pictures: bool,
+}
+
+impl Default for FfmpegOptions {
+    fn default() -> Self {
+        FfmpegOptions {
+            stream_index: None,
+            language: None,
+            title: None,
+            skip_attached_pictures: true,
+        }
+    }
+}
+
+/// An interface that is used for decoding a video stream using ffmpeg
 pub struct FfmpegDecoder {
     input_ctx: context::Input,
     decoder: decoder::Video,
+    /// Converts frames to `video_details`' format, if the source's isn't supported
+    /// or frames change format or size partway through the stream
+    scaler: Option<scaling::Context>,
+    /// The pixel format frames are copied from
+    format: format::Pixel,
     pub video_details: VideoDetails,
-    frameno: usize,
     stream_index: usize,
-    end_of_stream: bool,
+    /// The time base of the stream's timestamps
+    time_base: ffmpeg::Rational,
+    /// The presentation time of each frame read so far, in seconds
+    timestamps: Vec<f64>,
+    /// The timestamp of the first frame, which `timestamps` are relative to
+    first_timestamp: Option<i64>,
+    /// Whether the end of the input has been sent to the decoder
     eof_sent: bool,
 }
 
@@ -34,77 +71,78 @@ impl FfmpegDecoder {
     /// # Errors
     ///
     /// - If ffmpeg is not available or not working on the system
-    /// - If the source contains no video
-    /// - If the source has an unsupported pixel format
-    pub fn new<P: AsRef<Path>>(input: P) -> anyhow::Result<Self> {
+    /// - If the source contains no video stream matching `opts`
+    /// - If the source has a pixel format that can't be converted to planar YUV
+    pub fn new<P: AsRef<Path>>(input: P, opts: &FfmpegOptions) -> anyhow::Result<Self> {
         ffmpeg::init()?;
 
         let input_ctx = format::input(&input)?;
+        let stream_index = select_stream(&input_ctx, opts)?;
         let input = input_ctx
-            .streams()
-            .best(Type::Video)
-            .ok_or_else(|| anyhow::anyhow!("Could not find video stream"))?;
-        let stream_index = input.index();
+            .stream(stream_index)
+            .ok_or_else(|| anyhow::anyhow!("Could not find stream {stream_index}"))?;
         let mut context = ffmpeg::codec::context::Context::from_parameters(input.parameters())?;
         context.set_threading(threading::Config::kind(threading::Type::Frame));
         let mut decoder = context.decoder().video()?;
         decoder.set_parameters(input.parameters())?;
 
-        let frame_rate = input.avg_frame_rate();
+        // Variable frame rate streams may not have an average frame rate,
+        // in which case the lowest frame rate that can represent all timestamps is used
+        let frame_rate = match input.avg_frame_rate() {
+            rate if rate.numerator() > 0 && rate.denominator() > 0 => rate,
+            _ => input.rate(),
+        };
+        let time_base = input.time_base();
+        // Formats that can't be copied into planes directly are converted
+        // to the nearest planar YUV layout
+        let source_format = decoder.format();
+        let (format, scaler) = if planar_yuv_layout(source_format).is_some() {
+            (source_format, None)
+        } else {
+            let format = conversion_target(source_format)?;
+            let scaler = scaling::Context::get(
+                source_format,
+                decoder.width(),
+                decoder.height(),
+                format,
+                decoder.width(),
+                decoder.height(),
+                scaling::Flags::BILINEAR,
+            )?;
+            (format, Some(scaler))
+        };
+        let (bit_depth, chroma_sampling) = planar_yuv_layout(format)
+            .ok_or_else(|| anyhow::anyhow!("Unsupported pixel format {format:?}"))?;
         Ok(Self {
             video_details: VideoDetails {
                 width: decoder.width() as usize,
                 height: decoder.height() as usize,
-                bit_depth: match decoder.format() {
-                    format::pixel::Pixel::YUV420P
-                    | format::pixel::Pixel::YUV422P
-                    | format::pixel::Pixel::YUV444P
-                    | format::pixel::Pixel::YUVJ420P
-                    | format::pixel::Pixel::YUVJ422P
-                    | format::pixel::Pixel::YUVJ444P => 8,
-                    format::pixel::Pixel::YUV420P10LE
-                    | format::pixel::Pixel::YUV422P10LE
-                    | format::pixel::Pixel::YUV444P10LE => 10,
-                    format::pixel::Pixel::YUV420P12LE
-                    | format::pixel::Pixel::YUV422P12LE
-                    | format::pixel::Pixel::YUV444P12LE => 12,
-                    _ => {
-                        bail!("Unsupported pixel format {:?}", decoder.format());
-                    }
-                },
-                chroma_sampling: match decoder.format() {
-                    format::pixel::Pixel::YUV420P
-                    | format::pixel::Pixel::YUVJ420P
-                    | format::pixel::Pixel::YUV420P10LE
-                    | format::pixel::Pixel::YUV420P12LE => ChromaSampling::Cs420,
-                    format::pixel::Pixel::YUV422P
-                    | format::pixel::Pixel::YUVJ422P
-                    | format::pixel::Pixel::YUV422P10LE
-                    | format::pixel::Pixel::YUV422P12LE => ChromaSampling::Cs422,
-                    format::pixel::Pixel::YUV444P
-                    | format::pixel::Pixel::YUVJ444P
-                    | format::pixel::Pixel::YUV444P10LE
-                    | format::pixel::Pixel::YUV444P12LE => ChromaSampling::Cs444,
-                    _ => {
-                        bail!("Unsupported pixel format {:?}", decoder.format());
-                    }
-                },
-                chroma_sample_position: match decoder.format() {
-                    format::pixel::Pixel::YUV422P
-                    | format::pixel::Pixel::YUV422P10LE
-                    | format::pixel::Pixel::YUV422P12LE => ChromaSamplePosition::Vertical,
+                bit_depth,
+                chroma_sampling,
+                chroma_sample_position: match format {
+                    format::Pixel::YUV422P
+                    | format::Pixel::YUV422P10LE
+                    | format::Pixel::YUV422P12LE => ChromaSamplePosition::Vertical,
                     _ => ChromaSamplePosition::Colocated,
                 },
                 time_base: Rational::new(
                     frame_rate.denominator() as u64,
                     frame_rate.numerator() as u64,
                 ),
+                converted_from: scaler.is_some().then(|| {
+                    source_format
+                        .descriptor()
+                        .map_or("unknown", |desc| desc.name())
+                }),
             },
             decoder,
+            scaler,
+            format,
             input_ctx,
-            frameno: 0,
             stream_index,
-            end_of_stream: false,
+            time_base,
+            timestamps: Vec::new(),
+            first_timestamp: None,
             eof_sent: false,
         })
     }
@@ -122,75 +160,261 @@ impl FfmpegDecoder {
             self.video_details.chroma_sampling,
             LUMA_PADDING,
         );
-        let width = self.video_details.width;
-        let height = self.video_details.height;
-        let bit_depth = self.video_details.bit_depth;
-        let bytes = if bit_depth > 8 { 2 } else { 1 };
-        let (chroma_width, _) = self
-            .video_details
-            .chroma_sampling
-            .get_chroma_dimensions(width, height);
-        f.planes[0].copy_from_raw_u8(decoded.data(0), width * bytes, bytes);
-        f.planes[1].copy_from_raw_u8(decoded.data(1), chroma_width * bytes, bytes);
-        f.planes[2].copy_from_raw_u8(decoded.data(2), chroma_width * bytes, bytes);
+        let bytes = if self.video_details.bit_depth > 8 {
+            2
+        } else {
+            1
+        };
+        // Rows are usually padded for alignment, so each plane has its own stride
+        for (i, plane) in f.planes.iter_mut().enumerate() {
+            plane.copy_from_raw_u8(decoded.data(i), decoded.stride(i), bytes);
+        }
         f
     }
 
+    /// Reads the next frame, or `None` once the decoder has returned every frame.
+    ///
     /// # Errors
     ///
-    /// - If there are no frames remaining
-    pub fn read_video_frame<T: Pixel>(&mut self) -> anyhow::Result<Frame<T>> {
-        // For some reason there's a crap ton of work needed to get ffmpeg to do something simple,
-        // because each codec has it's own stupid way of doing things and they don't all
-        // decode the same way.
-        //
-        // Maybe ffmpeg could have made a simple, singular interface that does this for us,
-        // but noooooo.
+    /// - If the input can't be read or the decoder fails
+    pub fn read_video_frame<T: Pixel>(&mut self) -> anyhow::Result<Option<Frame<T>>> {
+        // A packet may decode to any number of frames, and decoders with reordering
+        // (e.g. B-frames) hold frames back until later packets or the end of the stream.
+        // So frames are drained from the decoder first, and packets are only sent
+        // when it asks for more input.
         //
-        // Reference: https://ffmpeg.org/doxygen/trunk/api-h264-test_8c_source.html#l00110
+        // Reference: https://ffmpeg.org/doxygen/trunk/group__lavc__encdec.html
         loop {
-            // This iterator is actually really stupid... it doesn't reset itself after each `new`.
-            // But that solves our lifetime hell issues, ironically.
-            let packet = self
-                .input_ctx
-                .packets()
-                .next()
-                .and_then(Result::ok)
-                .map(|(_, packet)| packet);
-
-            let mut packet = if let Some(packet) = packet {
-                packet
-            } else {
-                self.end_of_stream = true;
-                packet::Packet::empty()
-            };
-
-            if self.end_of_stream && !self.eof_sent {
-                let _ = self.decoder.send_eof();
-                self.eof_sent = true;
+            let mut decoded = frame::Video::empty();
+            match self.decoder.receive_frame(&mut decoded) {
+                Ok(()) => {
+                    let f = if decoded.format() == self.format
+                        && decoded.width() as usize == self.video_details.width
+                        && decoded.height() as usize == self.video_details.height
+                    {
+                        self.decode_frame(&decoded)
+                    } else {
+                        let mut converted = frame::Video::empty();
+                        self.scaler_for(&decoded)?.run(&decoded, &mut converted)?;
+                        self.decode_frame(&converted)
+                    };
+                    self.record_timestamp(&decoded);
+                    return Ok(Some(f));
+                }
+                Err(ffmpeg::Error::Eof) => return Ok(None),
+                Err(ffmpeg::Error::Other { errno }) if errno == ffmpeg::error::EAGAIN => {
+                    self.send_next_packet()?;
+                }
+                Err(e) => return Err(e.into()),
             }
+        }
+    }
 
-            if self.end_of_stream || packet.stream() == self.stream_index {
-                let mut decoded = frame::Video::new(
-                    self.decoder.format(),
-                    self.video_details.width as u32,
-                    self.video_details.height as u32,
-                );
-                packet.set_pts(Some(self.frameno as i64));
-                packet.set_dts(Some(self.frameno as i64));
-
-                if !self.end_of_stream {
-                    let _ = self.decoder.send_packet(&packet);
-                }
+    /// Returns a scaler converting `decoded` to the format and size
+    /// of `video_details`, rebuilding it if the format or size of the
+    /// decoded frames has changed.
+    fn scaler_for(&mut self, decoded: &frame::Video) -> anyhow::Result<&mut scaling::Context> {
+        let source = (decoded.format(), decoded.width(), decoded.height());
+        match self.scaler.take() {
+            Some(scaler)
+                if (
+                    scaler.input().format,
+                    scaler.input().width,
+                    scaler.input().height,
+                ) == source =>
+            {
+                Ok(self.scaler.insert(scaler))
+            }
+            _ => Ok(self.scaler.insert(scaling::Context::get(
+                source.0,
+                source.1,
+                source.2,
+                self.format,
+                self.video_details.width as u32,
+                self.video_details.height as u32,
+                scaling::Flags::BILINEAR,
+            )?)),
+        }
+    }
 
-                if self.decoder.receive_frame(&mut decoded).is_ok() {
-                    let f = self.decode_frame(&decoded);
-                    self.frameno += 1;
-                    return Ok(f);
-                } else if self.end_of_stream {
-                    bail!("No frames left");
+    /// Sends the next packet of the video stream to the decoder,
+    /// or flushes it at the end of the input.
+    fn send_next_packet(&mut self) -> anyhow::Result<()> {
+        loop {
+            let Some(result) = self.input_ctx.packets().next() else {
+                if self.eof_sent {
+                    bail!("Decoder asked for more input after the end of the stream");
                 }
+                self.decoder.send_eof()?;
+                self.eof_sent = true;
+                return Ok(());
+            };
+            let (stream, packet) = result?;
+            if stream.index() != self.stream_index {
+                continue;
             }
+            // The decoder has been drained, so it can't refuse input with `EAGAIN`
+            match self.decoder.send_packet(&packet) {
+                Ok(()) => return Ok(()),
+                // Skip corrupt packets, like ffmpeg does
+                Err(ffmpeg::Error::InvalidData) => continue,
+                Err(e) => return Err(e.into()),
+            }
+        }
+    }
+
+    /// The presentation time of each frame read so far, in seconds
+    /// from the first frame.
+    ///
+    /// These are taken from the stream, so they are accurate
+    /// for variable frame rate video, unlike frame numbers divided by
+    /// the frame rate in `video_details.time_base`.
+    #[must_use]
+    pub fn timestamps(&self) -> &[f64] {
+        &self.timestamps
+    }
+
+    fn record_timestamp(&mut self, decoded: &frame::Video) {
+        let seconds = if let Some(timestamp) = decoded.timestamp().or_else(|| decoded.pts()) {
+            let first = *self.first_timestamp.get_or_insert(timestamp);
+            (timestamp - first) as f64 * f64::from(self.time_base)
+        } else {
+            // Without a timestamp, assume the frame follows the previous one
+            // at the average frame rate
+            let frame_duration =
+                self.video_details.time_base.num as f64 / self.video_details.time_base.den as f64;
+            self.timestamps
+                .last()
+                .map_or(0.0, |previous| previous + frame_duration)
+        };
+        self.timestamps.push(seconds);
+    }
+}
+
+/// Returns the index of the video stream of `input_ctx` to decode
+fn select_stream(input_ctx: &context::Input, opts: &FfmpegOptions) -> anyhow::Result<usize> {
+    if let Some(index) = opts.stream_index {
+        let Some(stream) = input_ctx.stream(index) else {
+            bail!("Could not find stream {index}");
+        };
+        if stream.parameters().medium() != Type::Video {
+            bail!("Stream {index} is not a video stream");
         }
+        return Ok(index);
+    }
+
+    let title = opts.title.as_deref().map(str::to_lowercase);
+    let candidates = input_ctx
+        .streams()
+        .filter(|stream| stream.parameters().medium() == Type::Video)
+        .filter(|stream| {
+            !opts.skip_attached_pictures
+                || !stream
+                    .disposition()
+                    .contains(format::stream::Disposition::ATTACHED_PIC)
+        })
+        .filter(|stream| {
+            let metadata = stream.metadata();
+            opts.language.as_deref().is_none_or(|language| {
+                metadata
+                    .get("language")
+                    .is_some_and(|tag| tag.eq_ignore_ascii_case(language))
+            }) && title.as_deref().is_none_or(|title| {
+                metadata
+                    .get("title")
+                    .is_some_and(|tag| tag.to_lowercase().contains(title))
+            })
+        })
+        .map(|stream| stream.index())
+        .collect::<Vec<_>>();
+
+    // Prefer ffmpeg's choice if it matches, and the first matching stream otherwise
+    let best = input_ctx
+        .streams()
+        .best(Type::Video)
+        .map(|stream| stream.index());
+    match (best, candidates.first()) {
+        (Some(best), _) if candidates.contains(&best) => Ok(best),
+        (_, Some(&first)) => Ok(first),
+        (_, None) => bail!("Could not find a video stream matching {opts:?}"),
     }
 }
+
+/// The bit depth and chroma sampling of the planar YUV formats
+/// that frames can be copied from directly
+fn planar_yuv_layout(format: format::Pixel) -> Option<(usize, ChromaSampling)> {
+    Some(match format {
+        format::Pixel::YUV420P | format::Pixel::YUVJ420P => (8, ChromaSampling::Cs420),
+        format::Pixel::YUV422P | format::Pixel::YUVJ422P => (8, ChromaSampling::Cs422),
+        format::Pixel::YUV444P | format::Pixel::YUVJ444P => (8, ChromaSampling::Cs444),
+        format::Pixel::YUV420P10LE => (10, ChromaSampling::Cs420),
+        format::Pixel::YUV422P10LE => (10, ChromaSampling::Cs422),
+        format::Pixel::YUV444P10LE => (10, ChromaSampling::Cs444),
+        format::Pixel::YUV420P12LE => (12, ChromaSampling::Cs420),
+        format::Pixel::YUV422P12LE => (12, ChromaSampling::Cs422),
+        format::Pixel::YUV444P12LE => (12, ChromaSampling::Cs444),
+        _ => return None,
+    })
+}
+
+/// The supported planar YUV format that is closest to `format`,
+/// keeping its chroma resolution and as much of its bit depth as possible
+fn conversion_target(format: format::Pixel) -> anyhow::Result<format::Pixel> {
+    Ok(match format {
+        format::Pixel::NV12 | format::Pixel::NV21 | format::Pixel::GRAY8 => format::Pixel::YUV420P,
+        format::Pixel::P010LE | format::Pixel::P010BE | format::Pixel::YUV420P10BE => {
+            format::Pixel::YUV420P10LE
+        }
+        format::Pixel::P016LE
+        | format::Pixel::P016BE
+        | format::Pixel::YUV420P12BE
+        | format::Pixel::YUV420P16LE
+        | format::Pixel::YUV420P16BE => format::Pixel::YUV420P12LE,
+        format::Pixel::NV16 => format::Pixel::YUV422P,
+        format::Pixel::NV20LE | format::Pixel::NV20BE | format::Pixel::YUV422P10BE => {
+            format::Pixel::YUV422P10LE
+        }
+        format::Pixel::YUV422P12BE | format::Pixel::YUV422P16LE | format::Pixel::YUV422P16BE => {
+            format::Pixel::YUV422P12LE
+        }
+        format::Pixel::NV24
+        | format::Pixel::NV42
+        | format::Pixel::RGB24
+        | format::Pixel::BGR24
+        | format::Pixel::RGBA
+        | format::Pixel::BGRA
+        | format::Pixel::ARGB
+        | format::Pixel::ABGR
+        | format::Pixel::GBRP => format::Pixel::YUV444P,
+        format::Pixel::YUV444P10BE | format::Pixel::GBRP10LE | format::Pixel::GBRP10BE => {
+            format::Pixel::YUV444P10LE
+        }
+        format::Pixel::YUV444P12BE
+        | format::Pixel::YUV444P16LE
+        | format::Pixel::YUV444P16BE
+        | format::Pixel::GBRP12LE
+        | format::Pixel::GBRP12BE
+        | format::Pixel::RGB48LE
+        | format::Pixel::RGB48BE => format::Pixel::YUV444P12LE,
+        // Otherwise keep the chroma subsampling and bit depth of the format
+        _ => {
+            let Some(descriptor) = format.descriptor() else {
+                bail!("Unsupported pixel format {format:?}");
+            };
+            // SAFETY: The descriptor points to one of ffmpeg's static descriptors
+            let bit_depth = unsafe { (*descriptor.as_ptr()).comp[0].depth };
+            let chroma = (descriptor.log2_chroma_w(), descriptor.log2_chroma_h());
+            match (chroma, bit_depth) {
+                ((_, 1..), ..=8) => format::Pixel::YUV420P,
+                ((_, 1..), 9..=10) => format::Pixel::YUV420P10LE,
+                ((_, 1..), _) => format::Pixel::YUV420P12LE,
+                ((1.., 0), ..=8) => format::Pixel::YUV422P,
+                ((1.., 0), 9..=10) => format::Pixel::YUV422P10LE,
+                ((1.., 0), _) => format::Pixel::YUV422P12LE,
+                ((0, 0), ..=8) => format::Pixel::YUV444P,
+                ((0, 0), 9..=10) => format::Pixel::YUV444P10LE,
+                ((0, 0), _) => format::Pixel::YUV444P12LE,
+            }
+        }
+    })
+}
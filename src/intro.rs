@@ -0,0 +1,221 @@
+//! Finding the intro and credits several episodes of a show have in common,
+//! such as for "skip intro" markers.
+//!
+//! Episodes are compared by the hashes of their scenes, so they need to be
+//! detected with `DetectionOptions::scene_hashes`.
+
+use anyhow::{anyhow, ensure};
+
+use crate::DetectionResults;
+
+/// The detected scenes of an episode.
+#[derive(Debug, Clone)]
+pub struct Episode {
+    /// Results detected with `DetectionOptions::scene_hashes`.
+    pub results: DetectionResults,
+    /// The frame rate of the episode, used to report timestamps.
+    pub fps: f64,
+}
+
+/// Options determining how shared scenes are found.
+#[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serialize", serde(default))]
+pub struct IntroOptions {
+    /// How far from the start of each episode to look for the intro,
+    /// and from the end to look for the credits, in seconds.
+    pub search_duration: f64,
+    /// The maximum number of bits that can differ between the hashes
+    /// of matching scenes, out of 64.
+    pub max_distance: u32,
+    /// The minimum number of consecutive scenes shared by every episode.
+    pub min_scenes: usize,
+}
+
+impl Default for IntroOptions {
+    fn default() -> Self {
+        IntroOptions {
+            search_duration: 300.0,
+            max_distance: 10,
+            min_scenes: 2,
+        }
+    }
+}
+
+/// A range of frames in an episode.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize))]
+pub struct FrameRange {
+    /// The first frame of the range.
+    pub start_frame: usize,
+    /// The frame after the last frame of the range.
+    pub end_frame: usize,
+    /// The timestamp of `start_frame`, in seconds.
+    pub start_time: f64,
+    /// The timestamp of `end_frame`, in seconds.
+    pub end_time: f64,
+}
+
+/// The intro and credits found in an episode.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize))]
+pub struct EpisodeMarkers {
+    pub intro: Option<FrameRange>,
+    pub credits: Option<FrameRange>,
+}
+
+/// Finds the longest sequences of consecutive scenes shared by every episode
+/// near their start and near their end, returning the markers of each episode
+/// in the same order as `episodes`.
+///
+/// # Errors
+///
+/// - If there are fewer than two episodes.
+/// - If any episode was detected without scene hashes.
+pub fn find_intro_and_credits(
+    episodes: &[Episode],
+    opts: IntroOptions,
+) -> anyhow::Result<Vec<EpisodeMarkers>> {
+    ensure!(
+        episodes.len() >= 2,
+        "At least two episodes are needed to find their intro and credits"
+    );
+    let scenes = episodes
+        .iter()
+        .map(|episode| {
+            let hashes = episode.results.scene_hashes.as_deref().ok_or_else(|| {
+                anyhow!("Episodes must be detected with scene hashes to find their intro")
+            })?;
+            Ok(episode.scenes(hashes))
+        })
+        .collect::<anyhow::Result<Vec<_>>>()?;
+
+    let within = |episode: &Episode, scenes: &[Scene], from_end: bool| {
+        let limit = (opts.search_duration * episode.fps) as usize;
+        let frame_count = episode.results.frame_count;
+        scenes
+            .iter()
+            .copied()
+            .filter(|scene| {
+                if from_end {
+                    frame_count - scene.start <= limit
+                } else {
+                    scene.end <= limit
+                }
+            })
+            .collect::<Vec<_>>()
+    };
+    let intros = episodes
+        .iter()
+        .zip(&scenes)
+        .map(|(episode, scenes)| within(episode, scenes, false))
+        .collect::<Vec<_>>();
+    let credits = episodes
+        .iter()
+        .zip(&scenes)
+        .map(|(episode, scenes)| within(episode, scenes, true))
+        .collect::<Vec<_>>();
+    let intros = shared_sequence(&intros, opts);
+    let credits = shared_sequence(&credits, opts);
+
+    Ok(episodes
+        .iter()
+        .enumerate()
+        .map(|(i, episode)| EpisodeMarkers {
+            intro: intros.as_ref().map(|ranges| episode.range(ranges[i])),
+            credits: credits.as_ref().map(|ranges| episode.range(ranges[i])),
+        })
+        .collect())
+}
+
+/// A scene of an episode.
+#[derive(Debug, Clone, Copy)]
+struct Scene {
+    start: usize,
+    end: usize,
+    hash: u64,
+}
+
+impl Episode {
+    fn scenes(&self, hashes: &[u64]) -> Vec<Scene> {
+        let scene_changes = &self.results.scene_changes;
+        scene_changes
+            .iter()
+            .zip(hashes)
+            .enumerate()
+            .map(|(i, (&start, &hash))| Scene {
+                start,
+                end: scene_changes
+                    .get(i + 1)
+                    .copied()
+                    .unwrap_or(self.results.frame_count),
+                hash,
+            })
+            .collect()
+    }
+
+    fn range(&self, (start_frame, end_frame): (usize, usize)) -> FrameRange {
+        FrameRange {
+            start_frame,
+            end_frame,
+            start_time: start_frame as f64 / self.fps,
+            end_time: end_frame as f64 / self.fps,
+        }
+    }
+}
+
+/// Finds the sequence of consecutive scenes in the first episode that is also
+/// in every other episode and covers the most frames, returning the frames
+/// it covers in each episode.
+fn shared_sequence(episodes: &[Vec<Scene>], opts: IntroOptions) -> Option<Vec<(usize, usize)>> {
+    let (reference, others) = episodes.split_first()?;
+    let matches = |a: &Scene, b: &Scene| (a.hash ^ b.hash).count_ones() <= opts.max_distance;
+
+    // For each other episode, the longest run of matching scenes ending at each
+    // scene of the reference, and the scene of the other episode it ends at
+    let runs = others
+        .iter()
+        .map(|other| {
+            let mut previous = vec![0; other.len()];
+            reference
+                .iter()
+                .map(|scene| {
+                    let current = other
+                        .iter()
+                        .enumerate()
+                        .map(|(j, other_scene)| {
+                            if matches(scene, other_scene) {
+                                j.checked_sub(1).map_or(0, |j| previous[j]) + 1
+                            } else {
+                                0
+                            }
+                        })
+                        .collect::<Vec<usize>>();
+                    let longest = current
+                        .iter()
+                        .copied()
+                        .enumerate()
+                        .max_by_key(|&(_, length)| length)
+                        .map_or((0, 0), |(j, length)| (length, j));
+                    previous = current;
+                    longest
+                })
+                .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
+
+    let (end, length) = (0..reference.len())
+        .map(|i| {
+            let length = runs.iter().map(|runs| runs[i].0).min().unwrap_or(0);
+            (i, length)
+        })
+        .filter(|&(_, length)| length >= opts.min_scenes.max(1))
+        .max_by_key(|&(i, length)| reference[i].end - reference[i + 1 - length].start)?;
+
+    let mut ranges = vec![(reference[end + 1 - length].start, reference[end].end)];
+    for (other, runs) in others.iter().zip(&runs) {
+        let other_end = runs[end].1;
+        ranges.push((other[other_end + 1 - length].start, other[other_end].end));
+    }
+    Some(ranges)
+}
@@ -40,7 +40,7 @@
 #![deny(clippy::path_buf_push_overwrite)]
 #![deny(clippy::same_functions_in_if_condition)]
 #![warn(clippy::suspicious_operation_groupings)]
-#![deny(clippy::unchecked_duration_subtraction)]
+#![deny(clippy::unchecked_time_subtraction)]
 #![deny(clippy::unicode_not_nfc)]
 // Clarity/formatting lints
 #![warn(clippy::borrow_as_ptr)]
@@ -89,10 +89,23 @@
 #![warn(clippy::missing_errors_doc)]
 #![warn(clippy::missing_panics_doc)]
 
+mod analyze;
+pub mod compare;
 pub mod decoder;
+pub mod edl;
+pub mod eval;
 
 #[cfg(feature = "ffmpeg")]
 pub mod ffmpeg;
+pub mod intro;
+#[cfg(feature = "serialize")]
+pub mod otio;
+#[cfg(feature = "serialize")]
+pub mod serve;
+pub mod synthetic;
+#[cfg(feature = "image")]
+pub mod thumbnail;
+pub mod tune;
 #[cfg(feature = "vapoursynth")]
 pub mod vapoursynth;
 mod y4m;
@@ -100,24 +113,40 @@ mod y4m;
 use std::{
     collections::{BTreeMap, BTreeSet},
     io::Read,
+    ops::Range,
     sync::Arc,
     time::Instant,
 };
 
 pub use ::y4m::Decoder as Y4mDecoder;
+pub use analyze::{Algorithm, EnsembleMember, EnsembleOptions, FrameScores, RepresentativeFrame};
+use analyze::{
+    Detector, FrameScorer, PostProcessor, RepresentativeFrames, SceneClusters, SceneHashes,
+};
+use anyhow::ensure;
 use decoder::Decoder;
 pub use rav1e::scenechange::SceneChangeDetector;
 use rav1e::{
     config::{CpuFeatureLevel, EncoderConfig},
-    prelude::{Pixel, Sequence},
+    prelude::{Frame, Pixel, Sequence},
 };
 
 /// Options determining how to run scene change detection.
 #[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serialize", serde(default, deny_unknown_fields))]
 pub struct DetectionOptions {
     /// The speed of detection algorithm to use.
     /// Slower algorithms are more accurate/better for use in encoders.
     pub analysis_speed: SceneDetectionSpeed,
+    /// The algorithm to use with `SceneDetectionSpeed::Fast`.
+    pub algorithm: Algorithm,
+    /// How much differences in each channel contribute
+    /// to the score of `Algorithm::Hsv`.
+    pub hsv_weights: HsvWeights,
+    /// Run several detectors and combine their decisions,
+    /// instead of the detector chosen by `analysis_speed` and `algorithm`.
+    pub ensemble: Option<EnsembleOptions>,
     /// Enabling this will utilize heuristics to avoid scenecuts
     /// that are too close to each other.
     /// This is generally useful if you want scenecut detection
@@ -128,25 +157,246 @@ pub struct DetectionOptions {
     pub min_scenecut_distance: Option<usize>,
     /// The maximum distance between two scene changes.
     pub max_scenecut_distance: Option<usize>,
+    /// The minimum length of a scene, enforced after detection.
+    ///
+    /// Unlike `min_scenecut_distance`, which ignores any scene change
+    /// too close to the previous one, each shorter scene is merged into
+    /// the neighbouring scene it is most similar to.
+    pub min_scene_length: Option<usize>,
+    /// The maximum length of a scene, enforced after detection.
+    ///
+    /// Unlike `max_scenecut_distance`, which forces a scene change exactly
+    /// at the limit, each longer scene is split at the frame that differs
+    /// least from the frame before it within `scene_split_window` frames
+    /// before the limit.
+    pub max_scene_length: Option<usize>,
+    /// How many frames before `max_scene_length` to look for a frame
+    /// to split at. Defaults to a quarter of `max_scene_length`.
+    pub scene_split_window: Option<usize>,
+    /// Split the clip into this many scenes, instead of using a threshold.
+    ///
+    /// The frames that differ most from the frame before them are picked
+    /// as scene changes, keeping at least `min_scenecut_distance` and
+    /// `min_scene_length` frames between them. The detector is not run,
+    /// but `min_scene_length` and `max_scene_length` are still enforced
+    /// on the result.
+    pub target_scene_count: Option<usize>,
+    /// Choose the frame that best represents each scene,
+    /// reported in `DetectionResults::representative_frames`.
+    pub representative_frame: Option<RepresentativeFrame>,
+    /// Group scenes that look alike, such as recurring camera angles,
+    /// reported in `DetectionResults::scene_clusters`.
+    ///
+    /// A scene joins the most similar cluster if the average difference
+    /// between their 8-bit colours is at most this much.
+    pub scene_cluster_threshold: Option<f64>,
+    /// Compute a perceptual hash of each scene, reported in
+    /// `DetectionResults::scene_hashes`, to find the same scenes in other clips
+    /// with [`compare::compare`].
+    pub scene_hashes: bool,
     /// The distance to look ahead in the video
-    /// for scene flash detection.
+    /// for scene flash detection, from 1 to [`MAX_LOOKAHEAD_DISTANCE`].
     ///
     /// Not used if `detect_flashes` is `false`.
     pub lookahead_distance: usize,
+    /// Multiplier for the detection threshold.
+    /// Values below 1.0 detect more scene changes,
+    /// values above 1.0 detect fewer.
+    ///
+    /// Only used with `SceneDetectionSpeed::Fast`,
+    /// rav1e's standard detector has fixed thresholds.
+    pub threshold_scale: f64,
+    /// Offset added to the detection threshold after `threshold_scale`
+    /// has been applied.
+    ///
+    /// Only used with `SceneDetectionSpeed::Fast`.
+    pub threshold_bias: f64,
+    /// The average difference in 8-bit pixel values between
+    /// two downscaled frames above which the fast detector considers
+    /// a scene change. This is multiplied by `bit_depth / 8` for higher
+    /// bit depths, as in rav1e.
+    ///
+    /// Used by `Algorithm::PixelDifference`, and as the minimum difference
+    /// a frame needs to be considered by `Algorithm::Adaptive`.
+    pub fast_pixel_threshold: f64,
+}
+
+impl DetectionOptions {
+    /// Adjusts the detection thresholds for a type of content.
+    ///
+    /// Only `fast_pixel_threshold` is changed, so `threshold_scale` and
+    /// `threshold_bias` are kept and still apply on top of the preset.
+    #[must_use]
+    pub fn with_tune(self, tune: ContentTune) -> Self {
+        let fast_pixel_threshold = match tune {
+            ContentTune::LiveAction => FAST_PIXEL_THRESHOLD,
+            ContentTune::Animation => 12.0,
+        };
+        DetectionOptions {
+            fast_pixel_threshold,
+            ..self
+        }
+    }
 }
 
 impl Default for DetectionOptions {
     fn default() -> Self {
         DetectionOptions {
             analysis_speed: SceneDetectionSpeed::Standard,
+            algorithm: Algorithm::PixelDifference,
+            hsv_weights: HsvWeights::default(),
+            ensemble: None,
             detect_flashes: true,
             lookahead_distance: 5,
             min_scenecut_distance: None,
             max_scenecut_distance: None,
+            min_scene_length: None,
+            max_scene_length: None,
+            scene_split_window: None,
+            target_scene_count: None,
+            representative_frame: None,
+            scene_cluster_threshold: None,
+            scene_hashes: false,
+            threshold_scale: 1.0,
+            threshold_bias: 0.0,
+            fast_pixel_threshold: FAST_PIXEL_THRESHOLD,
         }
     }
 }
 
+/// The default for `DetectionOptions::fast_pixel_threshold`,
+/// the same threshold used by rav1e.
+pub const FAST_PIXEL_THRESHOLD: f64 = 18.0;
+
+/// The largest supported `DetectionOptions::lookahead_distance`.
+/// Every frame within this distance is kept in memory.
+pub const MAX_LOOKAHEAD_DISTANCE: usize = 250;
+
+/// Relative weights of each channel for `Algorithm::Hsv`.
+#[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serialize", serde(default, deny_unknown_fields))]
+pub struct HsvWeights {
+    pub hue: f64,
+    pub saturation: f64,
+    pub value: f64,
+}
+
+impl Default for HsvWeights {
+    fn default() -> Self {
+        HsvWeights {
+            hue: 1.0,
+            saturation: 1.0,
+            value: 1.0,
+        }
+    }
+}
+
+/// Overrides for individual frames, applied on top of `DetectionOptions`
+/// by [`detect_scene_changes_with_overrides`] and
+/// [`FrameScores::scene_changes_with_overrides`].
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serialize", serde(default, deny_unknown_fields))]
+pub struct FrameOverrides {
+    /// Frames that are always scene changes, whatever the detector decides,
+    /// such as ad insertion points or chapter starts.
+    ///
+    /// Forced cuts count as scene changes for `min_scenecut_distance`
+    /// and `max_scenecut_distance`, and are never merged away
+    /// by `min_scene_length`.
+    pub forced_cuts: BTreeSet<usize>,
+    /// Ranges of frames that are never scene changes, such as the middle
+    /// of dialogue.
+    ///
+    /// These take precedence over `max_scenecut_distance` and
+    /// `max_scene_length`, but not over `forced_cuts` or the first frame.
+    pub forbidden_ranges: Vec<Range<usize>>,
+    /// Ranges of frames detected with different options,
+    /// such as opening titles or credits.
+    ///
+    /// Detection restarts at the start and end of each zone, so flashes
+    /// and `Algorithm::Adaptive` never look back across a boundary.
+    /// Flash detection still looks ahead across the end of a zone, up to
+    /// `lookahead_distance` frames. Where zones overlap, the earlier zone is used.
+    pub zones: Vec<Zone>,
+}
+
+impl FrameOverrides {
+    /// Whether `frameno` is in `forced_cuts`.
+    pub(crate) fn is_forced_cut(&self, frameno: usize) -> bool {
+        self.forced_cuts.contains(&frameno)
+    }
+
+    /// Whether `frameno` can't be a scene change because it is in
+    /// `forbidden_ranges`, and isn't forced to be one.
+    pub(crate) fn is_forbidden_cut(&self, frameno: usize) -> bool {
+        self.forbidden_ranges
+            .iter()
+            .any(|range| range.contains(&frameno))
+            && !self.is_forced_cut(frameno)
+    }
+
+    /// The index of the zone `frameno` is in, if any.
+    pub(crate) fn zone_at(&self, frameno: usize) -> Option<usize> {
+        self.zones
+            .iter()
+            .position(|zone| (zone.start..zone.end).contains(&frameno))
+    }
+
+    /// `opts` with the overrides of the zone at index `zone` applied.
+    pub(crate) fn options_in_zone(
+        &self,
+        opts: DetectionOptions,
+        zone: Option<usize>,
+    ) -> DetectionOptions {
+        let Some(zone) = zone.and_then(|zone| self.zones.get(zone)) else {
+            return opts;
+        };
+        DetectionOptions {
+            analysis_speed: zone.analysis_speed.unwrap_or(opts.analysis_speed),
+            algorithm: zone.algorithm.unwrap_or(opts.algorithm),
+            detect_flashes: zone.detect_flashes.unwrap_or(opts.detect_flashes),
+            min_scenecut_distance: zone.min_scenecut_distance.or(opts.min_scenecut_distance),
+            max_scenecut_distance: zone.max_scenecut_distance.or(opts.max_scenecut_distance),
+            threshold_scale: zone.threshold_scale.unwrap_or(opts.threshold_scale),
+            ..opts
+        }
+    }
+}
+
+/// Options for detecting a range of frames, see `FrameOverrides::zones`.
+///
+/// Options left as `None` keep their value from `DetectionOptions`.
+/// `analysis_speed` and `algorithm` are ignored with ensemble detection.
+#[derive(Debug, Clone, Copy, Default)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serialize", serde(default, deny_unknown_fields))]
+pub struct Zone {
+    /// The first frame of the zone.
+    pub start: usize,
+    /// The frame after the last frame of the zone.
+    pub end: usize,
+    pub analysis_speed: Option<SceneDetectionSpeed>,
+    pub algorithm: Option<Algorithm>,
+    pub detect_flashes: Option<bool>,
+    pub min_scenecut_distance: Option<usize>,
+    pub max_scenecut_distance: Option<usize>,
+    pub threshold_scale: Option<f64>,
+}
+
+/// Presets for `DetectionOptions` suited to a type of content.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serialize", derive(serde::Deserialize))]
+pub enum ContentTune {
+    /// Camera footage. rav1e's thresholds were tuned for this,
+    /// so this matches the default options.
+    LiveAction,
+    /// Animation, where flat shading makes cuts between
+    /// similarly coloured shots produce small pixel differences.
+    Animation,
+}
+
 /// Results from a scene change detection pass.
 #[derive(Debug, Clone)]
 #[cfg_attr(feature = "serialize", derive(serde::Serialize))]
@@ -157,6 +407,30 @@ pub struct DetectionResults {
     pub frame_count: usize,
     /// Average speed (FPS)
     pub speed: f64,
+    /// With ensemble detection, the detectors that voted
+    /// for each entry in `scene_changes`.
+    #[cfg_attr(feature = "serialize", serde(skip_serializing_if = "Option::is_none"))]
+    pub ensemble_votes: Option<Vec<Vec<EnsembleMember>>>,
+    /// With `DetectionOptions::representative_frame`, the frame number
+    /// of the frame that best represents each scene in `scene_changes`.
+    #[cfg_attr(feature = "serialize", serde(skip_serializing_if = "Option::is_none"))]
+    pub representative_frames: Option<Vec<usize>>,
+    /// With `DetectionOptions::scene_cluster_threshold`, the cluster
+    /// of each scene in `scene_changes`, numbered from 0 in order of appearance.
+    #[cfg_attr(feature = "serialize", serde(skip_serializing_if = "Option::is_none"))]
+    pub scene_clusters: Option<Vec<usize>>,
+    /// With `DetectionOptions::scene_hashes`, a 64-bit perceptual hash
+    /// of each scene in `scene_changes`.
+    #[cfg_attr(feature = "serialize", serde(skip_serializing_if = "Option::is_none"))]
+    pub scene_hashes: Option<Vec<u64>>,
+    /// With a decoder that reports timestamps, such as ffmpeg, the time
+    /// in seconds from the first frame of each entry in `scene_changes`,
+    /// or `None` for an entry past the last decoded frame.
+    ///
+    /// Unlike dividing frame numbers by the frame rate, these are accurate
+    /// for variable frame rate video.
+    #[cfg_attr(feature = "serialize", serde(skip_serializing_if = "Option::is_none"))]
+    pub scene_change_times: Option<Vec<Option<f64>>>,
 }
 
 /// # Errors
@@ -217,24 +491,81 @@ pub fn new_detector<R: Read, T: Pixel>(
 /// # Errors
 ///
 /// - If using a Vapoursynth script that contains an unsupported video format.
+/// - If a frame cannot be decoded.
+/// - If `opts.lookahead_distance` is 0 or above [`MAX_LOOKAHEAD_DISTANCE`].
+pub fn detect_scene_changes<R: Read, T: Pixel>(
+    dec: &mut Decoder<R>,
+    opts: DetectionOptions,
+    frame_limit: Option<usize>,
+    progress_callback: Option<&dyn Fn(usize, usize)>,
+) -> anyhow::Result<DetectionResults> {
+    detect_scene_changes_with_overrides::<R, T>(
+        dec,
+        opts,
+        &FrameOverrides::default(),
+        frame_limit,
+        progress_callback,
+    )
+}
+
+/// Runs [`detect_scene_changes`], forcing, forbidding or detecting
+/// scene changes with different options at the frames in `overrides`.
 ///
-/// # Panics
+/// # Errors
 ///
-/// - If `opts.lookahead_distance` is 0.
-#[allow(clippy::needless_pass_by_value)]
-pub fn detect_scene_changes<R: Read, T: Pixel>(
+/// - If using a Vapoursynth script that contains an unsupported video format.
+/// - If a frame cannot be decoded.
+/// - If `opts.lookahead_distance` is 0 or above [`MAX_LOOKAHEAD_DISTANCE`].
+pub fn detect_scene_changes_with_overrides<R: Read, T: Pixel>(
+    dec: &mut Decoder<R>,
+    opts: DetectionOptions,
+    overrides: &FrameOverrides,
+    frame_limit: Option<usize>,
+    progress_callback: Option<&dyn Fn(usize, usize)>,
+) -> anyhow::Result<DetectionResults> {
+    detect_scene_changes_with::<R, T>(
+        dec,
+        opts,
+        overrides,
+        frame_limit,
+        progress_callback,
+        &mut |_, _, _| (),
+    )
+}
+
+/// Runs [`detect_scene_changes_with_overrides`], passing each frame to `inspect_frame`
+/// once it has been analyzed, along with its frame number and whether it was
+/// detected as a scene change. Post-processing may still change the
+/// scene changes after the last frame.
+pub(crate) fn detect_scene_changes_with<R: Read, T: Pixel>(
     dec: &mut Decoder<R>,
     opts: DetectionOptions,
+    overrides: &FrameOverrides,
     frame_limit: Option<usize>,
     progress_callback: Option<&dyn Fn(usize, usize)>,
+    inspect_frame: &mut dyn FnMut(usize, &Frame<T>, bool),
 ) -> anyhow::Result<DetectionResults> {
-    assert!(opts.lookahead_distance >= 1);
+    ensure!(
+        (1..=MAX_LOOKAHEAD_DISTANCE).contains(&opts.lookahead_distance),
+        "lookahead_distance must be from 1 to {MAX_LOOKAHEAD_DISTANCE}, got {}",
+        opts.lookahead_distance
+    );
 
-    let mut detector = new_detector::<R, T>(dec, opts)?;
+    let mut zone = overrides.zone_at(0);
+    let mut detector = Detector::<T>::new(dec, overrides.options_in_zone(opts, zone))?;
     let video_details = dec.get_video_details()?;
+    let mut post_processor = PostProcessor::<T>::new(&video_details, opts);
     let mut frame_queue = BTreeMap::new();
     let mut keyframes = BTreeSet::new();
     keyframes.insert(0);
+    let mut ensemble_votes = opts.ensemble.map(|_| Vec::new());
+    let mut representative_frames = opts
+        .representative_frame
+        .map(|method| RepresentativeFrames::new(video_details, method));
+    let mut scene_clusters = opts
+        .scene_cluster_threshold
+        .map(|_| SceneClusters::new(video_details));
+    let mut scene_hashes = opts.scene_hashes.then(|| SceneHashes::new(&video_details));
 
     let start_time = Instant::now();
     let mut frameno = 0;
@@ -243,14 +574,11 @@ pub fn detect_scene_changes<R: Read, T: Pixel>(
         while next_input_frameno
             < (frameno + opts.lookahead_distance + 1).min(frame_limit.unwrap_or(usize::MAX))
         {
-            let frame = dec.read_video_frame(&video_details);
-            if let Ok(frame) = frame {
-                frame_queue.insert(next_input_frameno, Arc::new(frame));
-                next_input_frameno += 1;
-            } else {
-                // End of input
+            let Some(frame) = dec.read_video_frame(&video_details)? else {
                 break;
-            }
+            };
+            frame_queue.insert(next_input_frameno, Arc::new(frame));
+            next_input_frameno += 1;
         }
 
         // The frame_queue should start at whatever the previous frame was
@@ -262,18 +590,53 @@ pub fn detect_scene_changes<R: Read, T: Pixel>(
             // End of video
             break;
         }
-        if frameno == 0
-            || detector.analyze_next_frame(
+        if let (Some(post_processor), [previous, current, ..]) =
+            (post_processor.as_mut(), frame_set.as_slice())
+        {
+            if frameno > 0 {
+                post_processor.push(previous, current);
+            }
+        }
+        if overrides.zone_at(frameno) != zone {
+            // Start a new detector, so no frames from before the boundary are used
+            zone = overrides.zone_at(frameno);
+            detector = Detector::new(dec, overrides.options_in_zone(opts, zone))?;
+        }
+        // With a target scene count, scene changes are picked after reading the whole clip
+        let detected = frameno > 0
+            && opts.target_scene_count.is_none()
+            && detector.analyze_next_frame(
                 &frame_set,
                 frameno as u64,
                 *keyframes
                     .iter()
                     .last()
                     .expect("at least 1 keyframe should exist"),
-            )
-        {
+            );
+        let scene_change = frameno == 0
+            || overrides.is_forced_cut(frameno)
+            || (detected && !overrides.is_forbidden_cut(frameno));
+        if scene_change {
             keyframes.insert(frameno as u64);
+            if let Some(ensemble_votes) = ensemble_votes.as_mut() {
+                ensemble_votes.push(detector.votes().unwrap_or_default().to_vec());
+            }
+        }
+        let current = if frameno == 0 {
+            frame_set[0]
+        } else {
+            frame_set[1]
         };
+        if let Some(representative_frames) = representative_frames.as_mut() {
+            representative_frames.push(current);
+        }
+        if let Some(scene_clusters) = scene_clusters.as_mut() {
+            scene_clusters.push(current);
+        }
+        if let Some(scene_hashes) = scene_hashes.as_mut() {
+            scene_hashes.push(current);
+        }
+        inspect_frame(frameno, current, scene_change);
 
         if frameno > 0 {
             frame_queue.remove(&(frameno - 1));
@@ -289,16 +652,98 @@ pub fn detect_scene_changes<R: Read, T: Pixel>(
             }
         }
     }
+
+    let mut scene_changes = keyframes
+        .into_iter()
+        .map(|val| val as usize)
+        .collect::<Vec<_>>();
+    if let Some(post_processor) = post_processor {
+        let processed = post_processor.finish(&scene_changes, opts, overrides);
+        if let Some(votes) = ensemble_votes.as_mut() {
+            // Scene changes added by post-processing have no votes
+            *votes = processed
+                .iter()
+                .map(|frameno| {
+                    scene_changes
+                        .binary_search(frameno)
+                        .map_or_else(|_| Vec::new(), |i| votes[i].clone())
+                })
+                .collect();
+        }
+        scene_changes = processed;
+    }
+    let representative_frames = representative_frames
+        .map(|representative_frames| representative_frames.finish(&scene_changes));
+    let scene_clusters = scene_clusters
+        .zip(opts.scene_cluster_threshold)
+        .map(|(scene_clusters, threshold)| scene_clusters.finish(&scene_changes, threshold));
+    let scene_hashes = scene_hashes.map(|scene_hashes| scene_hashes.finish(&scene_changes));
+    let scene_change_times = dec.timestamps().map(|timestamps| {
+        scene_changes
+            .iter()
+            .map(|&frameno| timestamps.get(frameno).copied())
+            .collect()
+    });
     Ok(DetectionResults {
-        scene_changes: keyframes.into_iter().map(|val| val as usize).collect(),
+        scene_changes,
         frame_count: frameno,
         speed: frameno as f64 / start_time.elapsed().as_secs_f64(),
+        ensemble_votes,
+        representative_frames,
+        scene_clusters,
+        scene_hashes,
+        scene_change_times,
     })
 }
 
+/// Runs through a video clip, scoring every frame with each of the
+/// native algorithms without deciding on scene changes.
+///
+/// The returned scores can be turned into scene changes for any
+/// `DetectionOptions` with [`FrameScores::scene_changes`],
+/// which is much faster than decoding the clip again.
+///
+/// # Arguments
+///
+/// - `hsv_weights`: The weights to score `Algorithm::Hsv` with.
+/// - `progress_callback`: An optional callback that will fire after each frame
+///   is scored, with the number of frames scored.
+///
+/// # Errors
+///
+/// - If using a Vapoursynth script that contains an unsupported video format.
+/// - If a frame cannot be decoded.
+pub fn score_frames<R: Read, T: Pixel>(
+    dec: &mut Decoder<R>,
+    hsv_weights: HsvWeights,
+    frame_limit: Option<usize>,
+    progress_callback: Option<&dyn Fn(usize)>,
+) -> anyhow::Result<FrameScores> {
+    let video_details = dec.get_video_details()?;
+    let mut scorer = FrameScorer::<T>::new(&video_details, hsv_weights);
+    let mut previous = None;
+    let mut frameno = 0;
+    while frame_limit.is_none_or(|frame_limit| frameno < frame_limit) {
+        let Some(frame) = dec.read_video_frame::<T>(&video_details)? else {
+            break;
+        };
+        scorer.push(previous.as_ref(), &frame);
+        previous = Some(frame);
+
+        frameno += 1;
+        if let Some(progress_fn) = progress_callback {
+            progress_fn(frameno);
+        }
+    }
+    Ok(scorer.finish())
+}
+
 #[derive(Clone, Copy, Debug, PartialOrd, PartialEq, Eq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
 pub enum SceneDetectionSpeed {
-    /// Fastest scene detection using pixel-wise comparison
+    /// Fastest scene detection using the native algorithm selected by
+    /// `DetectionOptions::algorithm`, with thresholds configurable
+    /// through `DetectionOptions`
     Fast,
     /// Scene detection using motion vectors
     Standard,
@@ -40,7 +40,7 @@
 #![deny(clippy::path_buf_push_overwrite)]
 #![deny(clippy::same_functions_in_if_condition)]
 #![warn(clippy::suspicious_operation_groupings)]
-#![deny(clippy::unchecked_duration_subtraction)]
+#![deny(clippy::unchecked_time_subtraction)]
 #![deny(clippy::unicode_not_nfc)]
 // Clarity/formatting lints
 #![warn(clippy::borrow_as_ptr)]
@@ -91,20 +91,37 @@
 
 use std::{
     fs::File,
-    io::{self, BufReader, Read, Write},
+    io::{self, BufReader, BufWriter, Read, Write},
+    path::Path,
+    time::Instant,
 };
 
-use anyhow::Result;
+use anyhow::{anyhow, bail, Context, Result};
+#[cfg(feature = "ffmpeg")]
+use av_scenechange::ffmpeg::{FfmpegDecoder, FfmpegOptions};
+#[cfg(feature = "image")]
+use av_scenechange::thumbnail::{self, ThumbnailFrame, ThumbnailOptions};
 use av_scenechange::{
-    decoder::Decoder, detect_scene_changes, DetectionOptions, SceneDetectionSpeed,
+    compare,
+    decoder::Decoder,
+    detect_scene_changes, detect_scene_changes_with_overrides, edl, eval, intro, otio,
+    score_frames,
+    tune::{self, SweepRanges},
+    Algorithm, ContentTune, DetectionOptions, DetectionResults, EnsembleOptions, FrameOverrides,
+    FrameScores, HsvWeights, RepresentativeFrame, SceneDetectionSpeed,
 };
-use clap::Parser;
+use clap::{Parser, Subcommand, ValueEnum};
 
 #[derive(Parser, Debug)]
+#[clap(subcommand_negates_reqs = true)]
 struct Args {
-    /// Sets the input file to use
-    #[clap(value_parser)]
-    pub input: String,
+    #[clap(subcommand)]
+    pub command: Option<Command>,
+
+    /// Sets the input file to use. With the `ffmpeg` feature,
+    /// files other than y4m are decoded with ffmpeg.
+    #[clap(value_parser, required = true)]
+    pub input: Option<String>,
 
     /// Optional file to write results to
     #[clap(long, short, value_parser)]
@@ -126,6 +143,329 @@ struct Args {
     /// after which a scenecut will be forced
     #[clap(long, value_parser)]
     pub max_scenecut: Option<usize>,
+
+    /// Merges scenes shorter than this into the neighbouring scene they are
+    /// most similar to, after detection
+    #[clap(long, value_parser)]
+    pub min_scene_length: Option<usize>,
+
+    /// Splits scenes longer than this after detection, at the least
+    /// disruptive frame shortly before the limit
+    #[clap(long, value_parser)]
+    pub max_scene_length: Option<usize>,
+
+    /// How many frames before `--max-scene-length` to look for a frame to
+    /// split at, defaults to a quarter of `--max-scene-length`
+    #[clap(long, value_parser, requires = "max_scene_length")]
+    pub scene_split_window: Option<usize>,
+
+    /// Splits the clip into this many scenes at the most distinct frames,
+    /// instead of detecting scenecuts with a threshold
+    #[clap(long, value_parser)]
+    pub target_scene_count: Option<usize>,
+
+    /// Multiplier for the detection threshold, lower values detect more
+    /// scenecuts. Requires `--speed 1`
+    #[clap(long, value_parser)]
+    pub threshold: Option<f64>,
+
+    /// Adjusts detection thresholds for a type of content. Requires `--speed 1`
+    #[clap(long, value_enum)]
+    pub tune: Option<Tune>,
+
+    /// Native detection algorithm to use. Requires `--speed 1`
+    #[clap(long, value_enum)]
+    pub algorithm: Option<AlgorithmArg>,
+
+    /// Weights of hue, saturation and value for `--algorithm hsv`,
+    /// separated by commas
+    #[clap(long, value_parser, value_delimiter = ',')]
+    pub hsv_weights: Option<Vec<f64>>,
+
+    /// Run the standard, pixel-difference and histogram detectors,
+    /// keeping the scenecuts a majority of them agree on
+    #[clap(long)]
+    pub ensemble: bool,
+
+    /// Weights of the standard, pixel-difference, histogram, adaptive and hsv
+    /// detectors for `--ensemble`, separated by commas
+    #[clap(long, value_parser, value_delimiter = ',', requires = "ensemble")]
+    pub ensemble_weights: Option<Vec<f64>>,
+
+    /// Fraction of the total weight that must agree on a scenecut
+    /// for `--ensemble`
+    #[clap(long, value_parser, requires = "ensemble")]
+    pub ensemble_agreement: Option<f64>,
+
+    /// Read frame scores written by the `score` subcommand from the input,
+    /// deciding scenecuts without decoding the video. Requires `--speed 1`
+    #[clap(long)]
+    pub scores: bool,
+
+    /// Report the frame that best represents each scene
+    #[clap(long, value_enum)]
+    pub representative_frame: Option<RepresentativeFrameArg>,
+
+    /// Group scenes that look alike, such as recurring camera angles, joining
+    /// scenes whose 8-bit colours differ by at most this much on average
+    #[clap(long, value_parser)]
+    pub cluster_threshold: Option<f64>,
+
+    /// Report a perceptual hash of each scene, which the `compare` subcommand
+    /// uses to find the same scenes in other clips
+    #[clap(long)]
+    pub scene_hashes: bool,
+
+    /// Always place scenecuts at the frames in this file,
+    /// as JSON, CSV, EDL or OTIO
+    #[clap(long, value_parser)]
+    pub forced_cuts: Option<String>,
+
+    /// Never place scenecuts in the frame ranges in this file,
+    /// as JSON or CSV with the end of each range excluded
+    #[clap(long, value_parser)]
+    pub forbidden_ranges: Option<String>,
+
+    /// Detect ranges of frames with different options, read from a JSON
+    /// array of zones such as `[{"start": 0, "end": 900, "threshold_scale": 1.5}]`
+    #[clap(long, value_parser)]
+    pub zones: Option<String>,
+
+    /// Write the detected scenes to this file as a CMX3600 EDL
+    #[clap(long, value_parser)]
+    pub edl: Option<String>,
+
+    /// Write the detected scenes to this file as an OTIO timeline
+    #[clap(long, value_parser)]
+    pub otio: Option<String>,
+
+    /// Write a thumbnail of each scene to this directory
+    #[cfg(feature = "image")]
+    #[clap(long, value_parser)]
+    pub thumbnails: Option<String>,
+
+    /// Write a grid of the thumbnail of each scene to this file
+    #[cfg(feature = "image")]
+    #[clap(long, value_parser)]
+    pub contact_sheet: Option<String>,
+
+    /// Which frame of each scene to use for thumbnails
+    #[cfg(feature = "image")]
+    #[clap(long, value_enum, default_value_t = ThumbnailFrameArg::Middle)]
+    pub thumbnail_frame: ThumbnailFrameArg,
+
+    /// Image format of the thumbnails written to `--thumbnails`
+    #[cfg(feature = "image")]
+    #[clap(long, value_enum, default_value_t = ThumbnailFormat::Png)]
+    pub thumbnail_format: ThumbnailFormat,
+
+    /// Number of thumbnails in each row of `--contact-sheet`
+    #[cfg(feature = "image")]
+    #[clap(long, value_parser, default_value_t = 6)]
+    pub contact_sheet_columns: usize,
+
+    /// Decode the stream with this index, as listed by ffprobe,
+    /// instead of the best video stream
+    #[cfg(feature = "ffmpeg")]
+    #[clap(long, value_parser)]
+    pub stream: Option<usize>,
+
+    /// Decode the video stream whose language tag is this, e.g. eng
+    #[cfg(feature = "ffmpeg")]
+    #[clap(long, value_parser)]
+    pub stream_language: Option<String>,
+
+    /// Decode the video stream whose title tag contains this, ignoring case
+    #[cfg(feature = "ffmpeg")]
+    #[clap(long, value_parser)]
+    pub stream_title: Option<String>,
+
+    /// Consider attached pictures, such as cover art, when choosing a video stream
+    #[cfg(feature = "ffmpeg")]
+    #[clap(long)]
+    pub include_attached_pictures: bool,
+}
+
+#[cfg(feature = "image")]
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum ThumbnailFrameArg {
+    First,
+    Middle,
+    Sharpest,
+}
+
+#[cfg(feature = "image")]
+impl From<ThumbnailFrameArg> for ThumbnailFrame {
+    fn from(frame: ThumbnailFrameArg) -> Self {
+        match frame {
+            ThumbnailFrameArg::First => ThumbnailFrame::First,
+            ThumbnailFrameArg::Middle => ThumbnailFrame::Middle,
+            ThumbnailFrameArg::Sharpest => ThumbnailFrame::Sharpest,
+        }
+    }
+}
+
+#[cfg(feature = "image")]
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum ThumbnailFormat {
+    Png,
+    Jpg,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum AlgorithmArg {
+    PixelDifference,
+    Histogram,
+    Adaptive,
+    Hsv,
+}
+
+impl From<AlgorithmArg> for Algorithm {
+    fn from(algorithm: AlgorithmArg) -> Self {
+        match algorithm {
+            AlgorithmArg::PixelDifference => Algorithm::PixelDifference,
+            AlgorithmArg::Histogram => Algorithm::Histogram,
+            AlgorithmArg::Adaptive => Algorithm::Adaptive,
+            AlgorithmArg::Hsv => Algorithm::Hsv,
+        }
+    }
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum RepresentativeFrameArg {
+    /// The frame closest to the mean luma histogram of the scene
+    Histogram,
+    /// The frame with the most detail
+    Sharpness,
+}
+
+impl From<RepresentativeFrameArg> for RepresentativeFrame {
+    fn from(method: RepresentativeFrameArg) -> Self {
+        match method {
+            RepresentativeFrameArg::Histogram => RepresentativeFrame::Histogram,
+            RepresentativeFrameArg::Sharpness => RepresentativeFrame::Sharpness,
+        }
+    }
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum Tune {
+    Anime,
+    LiveAction,
+}
+
+impl From<Tune> for ContentTune {
+    fn from(tune: Tune) -> Self {
+        match tune {
+            Tune::Anime => ContentTune::Animation,
+            Tune::LiveAction => ContentTune::LiveAction,
+        }
+    }
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Run as a long-lived server, reading one JSON job per line
+    /// and writing one JSON result per line
+    Serve {
+        /// Listen on this Unix socket instead of using stdin and stdout
+        #[clap(long, value_parser)]
+        socket: Option<String>,
+    },
+    /// Compare detected scenecuts to a ground truth, reporting precision,
+    /// recall and F1 score
+    Eval {
+        /// Detected scenecuts, as JSON output by this tool, CSV, EDL or OTIO
+        #[clap(value_parser)]
+        detected: String,
+        /// Expected scenecuts, as JSON, CSV, EDL or OTIO
+        #[clap(value_parser)]
+        ground_truth: String,
+        /// Maximum distance in frames between matching scenecuts
+        #[clap(long, value_parser, default_value_t = 0)]
+        tolerance: usize,
+        /// Frame rate used to read EDL files
+        #[clap(long, value_parser)]
+        fps: Option<f64>,
+    },
+    /// Detect the scenes of two clips, printing the scenes they have in common
+    Compare {
+        /// The first clip
+        #[clap(value_parser)]
+        left: String,
+        /// The second clip
+        #[clap(value_parser)]
+        right: String,
+        /// Maximum number of bits that can differ between the hashes of
+        /// matching scenes, out of 64
+        #[clap(long, value_parser, default_value_t = 10)]
+        max_distance: u32,
+        /// Speed level for scene-change detection, 0: best quality, 1: fastest
+        /// mode
+        #[clap(long, short, value_parser, default_value_t = 0)]
+        speed: u8,
+    },
+    /// Detect the scenes of several episodes of a show, printing the frames
+    /// and timestamps of the intro and credits they have in common
+    Intro {
+        /// The episodes
+        #[clap(value_parser, num_args = 2..)]
+        inputs: Vec<String>,
+        /// How far from the start and end of each episode to look for the
+        /// intro and credits, in seconds
+        #[clap(long, value_parser, default_value_t = 300.0)]
+        search_duration: f64,
+        /// Maximum number of bits that can differ between the hashes of
+        /// matching scenes, out of 64
+        #[clap(long, value_parser, default_value_t = 10)]
+        max_distance: u32,
+        /// Minimum number of consecutive scenes every episode must share
+        #[clap(long, value_parser, default_value_t = 2)]
+        min_scenes: usize,
+        /// Speed level for scene-change detection, 0: best quality, 1: fastest
+        /// mode
+        #[clap(long, short, value_parser, default_value_t = 0)]
+        speed: u8,
+    },
+    /// Decode a clip, writing the frame scores used by the fast detector to a
+    /// file that can be used with `--scores`
+    Score {
+        /// The clip to score
+        #[clap(value_parser)]
+        input: String,
+        /// File to write scores to
+        #[clap(value_parser)]
+        output: String,
+        /// Weights of hue, saturation and value for the HSV scores,
+        /// separated by commas. These are used when the scores are read with
+        /// `--scores --algorithm hsv`
+        #[clap(long, value_parser, value_delimiter = ',')]
+        hsv_weights: Option<Vec<f64>>,
+    },
+    /// Decode a clip once, then try many combinations of options for the fast
+    /// detector, printing those that best match a ground truth
+    Tune {
+        /// The clip to tune for
+        #[clap(value_parser)]
+        input: String,
+        /// Expected scenecuts, as JSON, CSV, EDL or OTIO
+        #[clap(value_parser)]
+        ground_truth: String,
+        /// Maximum distance in frames between matching scenecuts
+        #[clap(long, value_parser, default_value_t = 0)]
+        tolerance: usize,
+        /// Frame rate used to read EDL files, defaults to the frame rate of the
+        /// clip when not using `--scores`
+        #[clap(long, value_parser)]
+        fps: Option<f64>,
+        /// Number of best configurations to print
+        #[clap(long, value_parser, default_value_t = 1)]
+        top: usize,
+        /// Read frame scores written by the `score` subcommand from the input
+        /// instead of decoding it
+        #[clap(long)]
+        scores: bool,
+    },
 }
 
 fn main() -> Result<()> {
@@ -141,45 +481,479 @@ fn main() -> Result<()> {
             .expect("Could not initialize tracing subscriber");
     }
 
-    let matches = Args::parse();
-    let input = match matches.input.as_str() {
-        "-" => Box::new(io::stdin()) as Box<dyn Read>,
-        f => Box::new(File::open(f)?) as Box<dyn Read>,
-    };
-    let mut reader = BufReader::new(input);
+    let mut matches = Args::parse();
+    match matches.command.take() {
+        Some(Command::Serve { socket }) => return serve(socket),
+        Some(Command::Eval {
+            detected,
+            ground_truth,
+            tolerance,
+            fps,
+        }) => {
+            let detected = eval::read_cut_list(detected, fps)?;
+            let ground_truth = eval::read_cut_list(ground_truth, fps)?;
+            let evaluation = eval::evaluate(&detected, &ground_truth, tolerance);
+            println!("{}", serde_json::to_string_pretty(&evaluation)?);
+            return Ok(());
+        }
+        Some(Command::Compare {
+            left,
+            right,
+            max_distance,
+            speed,
+        }) => {
+            let opts = DetectionOptions {
+                analysis_speed: parse_speed(speed)?,
+                scene_hashes: true,
+                ..DetectionOptions::default()
+            };
+            let (left, _) = detect_file(&left, opts, &matches)?;
+            let (right, _) = detect_file(&right, opts, &matches)?;
+            let comparison = compare::compare(&left, &right, max_distance)?;
+            println!("{}", serde_json::to_string_pretty(&comparison)?);
+            return Ok(());
+        }
+        Some(Command::Intro {
+            inputs,
+            search_duration,
+            max_distance,
+            min_scenes,
+            speed,
+        }) => {
+            let opts = DetectionOptions {
+                analysis_speed: parse_speed(speed)?,
+                scene_hashes: true,
+                ..DetectionOptions::default()
+            };
+            let episodes = inputs
+                .iter()
+                .map(|input| {
+                    let (results, fps) = detect_file(input, opts, &matches)?;
+                    Ok(intro::Episode { results, fps })
+                })
+                .collect::<Result<Vec<_>>>()?;
+            let markers = intro::find_intro_and_credits(
+                &episodes,
+                intro::IntroOptions {
+                    search_duration,
+                    max_distance,
+                    min_scenes,
+                },
+            )?;
+            let output = inputs
+                .iter()
+                .zip(markers)
+                .map(|(input, markers)| {
+                    serde_json::json!({
+                        "input": input,
+                        "intro": markers.intro,
+                        "credits": markers.credits,
+                    })
+                })
+                .collect::<Vec<_>>();
+            println!("{}", serde_json::to_string_pretty(&output)?);
+            return Ok(());
+        }
+        Some(Command::Score {
+            input,
+            output,
+            hsv_weights,
+        }) => {
+            let hsv_weights = hsv_weights
+                .as_deref()
+                .map(parse_hsv_weights)
+                .transpose()?
+                .unwrap_or_default();
+            let (scores, _) = read_scores(&input, false, hsv_weights, &matches)?;
+            scores.write_to(BufWriter::new(File::create(output)?))?;
+            return Ok(());
+        }
+        Some(Command::Tune {
+            input,
+            ground_truth,
+            tolerance,
+            fps,
+            top,
+            scores,
+        }) => {
+            let (scores, clip_fps) = read_scores(&input, scores, HsvWeights::default(), &matches)?;
+            return tune(&scores, ground_truth, tolerance, fps.or(clip_fps), top);
+        }
+        None => (),
+    }
+
+    let input = matches
+        .input
+        .as_deref()
+        .ok_or_else(|| anyhow!("An input file is required"))?;
 
     let mut opts = DetectionOptions {
         detect_flashes: !matches.no_flash_detection,
         min_scenecut_distance: matches.min_scenecut,
         max_scenecut_distance: matches.max_scenecut,
+        min_scene_length: matches.min_scene_length,
+        max_scene_length: matches.max_scene_length,
+        scene_split_window: matches.scene_split_window,
+        target_scene_count: matches.target_scene_count,
+        representative_frame: matches.representative_frame.map(Into::into),
+        scene_cluster_threshold: matches.cluster_threshold,
+        scene_hashes: matches.scene_hashes,
         ..DetectionOptions::default()
     };
 
-    opts.analysis_speed = match matches.speed {
+    opts.analysis_speed = parse_speed(matches.speed)?;
+    let mut overrides = FrameOverrides::default();
+    if let Some(zones) = matches.zones.as_deref() {
+        let zones =
+            std::fs::read_to_string(zones).with_context(|| format!("Could not read {zones}"))?;
+        overrides.zones = serde_json::from_str(&zones).context("Invalid zones")?;
+    }
+    if let Some(tune) = matches.tune {
+        if opts.analysis_speed != SceneDetectionSpeed::Fast {
+            bail!("--tune is only supported by the fast detector (--speed 1)");
+        }
+        opts = opts.with_tune(tune.into());
+    }
+    if let Some(threshold) = matches.threshold {
+        if opts.analysis_speed != SceneDetectionSpeed::Fast {
+            bail!("--threshold is only supported by the fast detector (--speed 1)");
+        }
+        opts.threshold_scale = threshold;
+    }
+    if let Some(algorithm) = matches.algorithm {
+        if opts.analysis_speed != SceneDetectionSpeed::Fast {
+            bail!("--algorithm is only supported by the fast detector (--speed 1)");
+        }
+        opts.algorithm = algorithm.into();
+    }
+    if let Some(hsv_weights) = matches.hsv_weights.as_deref() {
+        opts.hsv_weights = parse_hsv_weights(hsv_weights)?;
+    }
+    if matches.ensemble {
+        let mut ensemble = EnsembleOptions::default();
+        match matches.ensemble_weights.as_deref() {
+            None => (),
+            Some(&[standard, pixel_difference, histogram, adaptive, hsv]) => {
+                ensemble = EnsembleOptions {
+                    standard,
+                    pixel_difference,
+                    histogram,
+                    adaptive,
+                    hsv,
+                    ..ensemble
+                };
+            }
+            Some(_) => bail!("--ensemble-weights requires exactly 5 values"),
+        }
+        if let Some(min_agreement) = matches.ensemble_agreement {
+            ensemble.min_agreement = min_agreement;
+        }
+        opts.ensemble = Some(ensemble);
+    }
+
+    let (results, fps) = if matches.scores {
+        let standard_zone = overrides
+            .zones
+            .iter()
+            .any(|zone| zone.analysis_speed == Some(SceneDetectionSpeed::Standard));
+        if opts.analysis_speed != SceneDetectionSpeed::Fast
+            || opts.ensemble.is_some()
+            || standard_zone
+        {
+            bail!("--scores is only supported by the fast detector (--speed 1) without --ensemble");
+        }
+        if opts.representative_frame.is_some()
+            || opts.scene_cluster_threshold.is_some()
+            || opts.scene_hashes
+        {
+            bail!(
+                "--representative-frame, --cluster-threshold and --scene-hashes can't be used \
+                 with --scores"
+            );
+        }
+        if matches.edl.is_some() || matches.otio.is_some() {
+            bail!(
+                "--edl and --otio need the frame rate of the clip, so can't be used with --scores"
+            );
+        }
+        read_cut_constraints(&mut overrides, &matches, None)?;
+        let start_time = Instant::now();
+        let scores = FrameScores::read_from(open_input(input)?)?;
+        let results = DetectionResults {
+            scene_changes: scores.scene_changes_with_overrides(opts, &overrides),
+            frame_count: scores.frame_count(),
+            speed: scores.frame_count() as f64 / start_time.elapsed().as_secs_f64(),
+            ensemble_votes: None,
+            representative_frames: None,
+            scene_clusters: None,
+            scene_hashes: None,
+            scene_change_times: None,
+        };
+        (results, None)
+    } else {
+        let mut dec = open_decoder(input, &matches)?;
+        let video_details = dec.get_video_details()?;
+        let fps = video_details.time_base.den as f64 / video_details.time_base.num as f64;
+        read_cut_constraints(&mut overrides, &matches, Some(fps))?;
+        #[cfg(feature = "image")]
+        if matches.thumbnails.is_some() || matches.contact_sheet.is_some() {
+            return detect_with_thumbnails(&mut dec, opts, &overrides, &matches, fps);
+        }
+        let results = if video_details.bit_depth == 8 {
+            detect_scene_changes_with_overrides::<_, u8>(&mut dec, opts, &overrides, None, None)?
+        } else {
+            detect_scene_changes_with_overrides::<_, u16>(&mut dec, opts, &overrides, None, None)?
+        };
+        (results, Some(fps))
+    };
+    write_results(&results, &matches, fps)
+}
+
+/// The stream selection requested in `args`
+#[cfg(feature = "ffmpeg")]
+fn ffmpeg_options(args: &Args) -> FfmpegOptions {
+    FfmpegOptions {
+        stream_index: args.stream,
+        language: args.stream_language.clone(),
+        title: args.stream_title.clone(),
+        skip_attached_pictures: !args.include_attached_pictures,
+    }
+}
+
+/// Reads the forced cuts and forbidden ranges requested in `args` into `overrides`.
+/// `fps` is required to read forced cuts from an EDL.
+fn read_cut_constraints(
+    overrides: &mut FrameOverrides,
+    args: &Args,
+    fps: Option<f64>,
+) -> Result<()> {
+    if let Some(forced_cuts) = args.forced_cuts.as_deref() {
+        overrides.forced_cuts = eval::read_cut_list(forced_cuts, fps)?.into_iter().collect();
+    }
+    if let Some(forbidden_ranges) = args.forbidden_ranges.as_deref() {
+        overrides.forbidden_ranges = eval::read_frame_ranges(forbidden_ranges)?;
+    }
+    Ok(())
+}
+
+/// Prints `results`, also writing them to the files requested in `args`.
+/// `fps` is required to write an EDL or OTIO timeline.
+fn write_results(results: &DetectionResults, args: &Args, fps: Option<f64>) -> Result<()> {
+    print!("{}", serde_json::to_string(results)?);
+
+    if let Some(output_file) = args.output.as_deref() {
+        let mut file = File::create(output_file)?;
+
+        let output = serde_json::to_string_pretty(results)?;
+        file.write_all(&output.into_bytes())?;
+    }
+
+    // Name timelines after the input, and reference it unless read from stdin
+    let input = args.input.as_deref().filter(|&input| input != "-");
+    let name = input
+        .and_then(|input| Path::new(input).file_stem())
+        .map_or_else(|| "av-scenechange".into(), |stem| stem.to_string_lossy());
+    if let Some(edl_file) = args.edl.as_deref() {
+        let fps = fps.context("Writing an EDL requires a frame rate")?;
+        let clip_name = input
+            .and_then(|input| Path::new(input).file_name())
+            .map(|name| name.to_string_lossy());
+        let edl = edl::write(
+            &name,
+            &results.scene_changes,
+            results.frame_count,
+            fps,
+            clip_name.as_deref(),
+        );
+        std::fs::write(edl_file, edl)?;
+    }
+    if let Some(otio_file) = args.otio.as_deref() {
+        let fps = fps.context("Writing an OTIO timeline requires a frame rate")?;
+        let otio = otio::write(
+            &name,
+            &results.scene_changes,
+            results.frame_count,
+            fps,
+            input,
+        )?;
+        std::fs::write(otio_file, otio)?;
+    }
+
+    Ok(())
+}
+
+/// Detects scenecuts, writing the thumbnails and contact sheet requested in
+/// `args` along with the results.
+#[cfg(feature = "image")]
+fn detect_with_thumbnails<R: Read>(
+    dec: &mut Decoder<R>,
+    opts: DetectionOptions,
+    overrides: &FrameOverrides,
+    args: &Args,
+    fps: f64,
+) -> Result<()> {
+    let thumbnail_opts = ThumbnailOptions {
+        frame: args.thumbnail_frame.into(),
+        ..ThumbnailOptions::default()
+    };
+    let (results, thumbnails) = if dec.get_video_details()?.bit_depth == 8 {
+        thumbnail::detect_with_thumbnails::<_, u8>(
+            dec,
+            opts,
+            overrides,
+            thumbnail_opts,
+            None,
+            None,
+        )?
+    } else {
+        thumbnail::detect_with_thumbnails::<_, u16>(
+            dec,
+            opts,
+            overrides,
+            thumbnail_opts,
+            None,
+            None,
+        )?
+    };
+
+    if let Some(dir) = args.thumbnails.as_deref() {
+        std::fs::create_dir_all(dir)?;
+        let extension = match args.thumbnail_format {
+            ThumbnailFormat::Png => "png",
+            ThumbnailFormat::Jpg => "jpg",
+        };
+        for thumbnail in &thumbnails {
+            let path =
+                Path::new(dir).join(format!("scene-{:06}.{extension}", thumbnail.scene_start));
+            thumbnail.save(path)?;
+        }
+    }
+    if let Some(path) = args.contact_sheet.as_deref() {
+        thumbnail::contact_sheet(&thumbnails, args.contact_sheet_columns, 240)?.save(path)?;
+    }
+    write_results(&results, args, Some(fps))
+}
+
+/// Opens `input` with the decoder for its format: y4m for stdin and `.y4m`
+/// files, and ffmpeg for anything else if it is enabled, with the stream
+/// selection requested in `args`.
+#[cfg_attr(not(feature = "ffmpeg"), allow(unused_variables))]
+fn open_decoder(input: &str, args: &Args) -> Result<Decoder<BufReader<Box<dyn Read>>>> {
+    #[cfg(feature = "ffmpeg")]
+    if input != "-"
+        && !Path::new(input)
+            .extension()
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("y4m"))
+    {
+        return Ok(Decoder::Ffmpeg(FfmpegDecoder::new(
+            input,
+            &ffmpeg_options(args),
+        )?));
+    }
+    Ok(Decoder::Y4m(y4m::Decoder::new(open_input(input)?)?))
+}
+
+fn open_input(input: &str) -> Result<BufReader<Box<dyn Read>>> {
+    let input = match input {
+        "-" => Box::new(io::stdin()) as Box<dyn Read>,
+        f => Box::new(File::open(f)?) as Box<dyn Read>,
+    };
+    Ok(BufReader::new(input))
+}
+
+fn parse_speed(speed: u8) -> Result<SceneDetectionSpeed> {
+    Ok(match speed {
         0 => SceneDetectionSpeed::Standard,
         1 => SceneDetectionSpeed::Fast,
-        _ => panic!("Speed mode must be in range [0; 1]"),
-    };
+        _ => bail!("Speed mode must be in range [0; 1]"),
+    })
+}
 
-    let mut dec = Decoder::Y4m(y4m::Decoder::new(&mut reader)?);
-    let bit_depth = dec.get_video_details()?.bit_depth;
-    let results = if bit_depth == 8 {
+/// Decodes `input` and detects its scene changes,
+/// returning the results and frame rate.
+fn detect_file(
+    input: &str,
+    opts: DetectionOptions,
+    args: &Args,
+) -> Result<(DetectionResults, f64)> {
+    let mut dec = open_decoder(input, args)?;
+    let video_details = dec.get_video_details()?;
+    let results = if video_details.bit_depth == 8 {
         detect_scene_changes::<_, u8>(&mut dec, opts, None, None)?
     } else {
         detect_scene_changes::<_, u16>(&mut dec, opts, None, None)?
     };
-    print!("{}", serde_json::to_string(&results)?);
+    let time_base = video_details.time_base;
+    Ok((results, time_base.den as f64 / time_base.num as f64))
+}
 
-    if let Some(output_file) = matches.output {
-        let mut file = File::create(output_file)?;
+fn parse_hsv_weights(values: &[f64]) -> Result<HsvWeights> {
+    let &[hue, saturation, value] = values else {
+        bail!("--hsv-weights requires exactly 3 values");
+    };
+    Ok(HsvWeights {
+        hue,
+        saturation,
+        value,
+    })
+}
 
-        let output = serde_json::to_string_pretty(&results)?;
-        file.write_all(&output.into_bytes())?;
+/// Decodes `input` and scores its frames, returning the scores and frame rate,
+/// or reads scores from it if `is_scores` is set.
+fn read_scores(
+    input: &str,
+    is_scores: bool,
+    hsv_weights: HsvWeights,
+    args: &Args,
+) -> Result<(FrameScores, Option<f64>)> {
+    if is_scores {
+        return Ok((FrameScores::read_from(open_input(input)?)?, None));
     }
+    let mut dec = open_decoder(input, args)?;
+    let video_details = dec.get_video_details()?;
+    let scores = if video_details.bit_depth == 8 {
+        score_frames::<_, u8>(&mut dec, hsv_weights, None, None)?
+    } else {
+        score_frames::<_, u16>(&mut dec, hsv_weights, None, None)?
+    };
+    let time_base = video_details.time_base;
+    Ok((scores, Some(time_base.den as f64 / time_base.num as f64)))
+}
 
+fn tune(
+    scores: &FrameScores,
+    ground_truth: String,
+    tolerance: usize,
+    fps: Option<f64>,
+    top: usize,
+) -> Result<()> {
+    let ground_truth = eval::read_cut_list(ground_truth, fps)?;
+    let results = tune::sweep(
+        scores,
+        DetectionOptions::default(),
+        &SweepRanges::default(),
+        &ground_truth,
+        tolerance,
+    );
+    let best = results.get(..top).unwrap_or(&results);
+    println!("{}", serde_json::to_string_pretty(best)?);
     Ok(())
 }
 
+fn serve(socket: Option<String>) -> Result<()> {
+    let Some(path) = socket else {
+        return av_scenechange::serve::serve(io::stdin().lock(), io::stdout().lock());
+    };
+
+    #[cfg(unix)]
+    return av_scenechange::serve::serve_unix_socket(path);
+
+    #[cfg(not(unix))]
+    Err(anyhow!(
+        "Unix sockets are not supported on this platform, cannot listen on {path}"
+    ))
+}
+
 #[cfg(not(feature = "devel"))]
 const fn init_logger() {
     // Do nothing
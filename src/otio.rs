@@ -0,0 +1,177 @@
+//! Reading and writing `OpenTimelineIO` (`.otio`) timelines.
+
+use anyhow::{ensure, Context};
+use serde_json::{json, Value};
+
+/// Writes the scenes starting at `scene_changes` as the clips of
+/// a single video track, so the cuts can be imported into an NLE.
+///
+/// Each clip references the media at `target_url` if given,
+/// and a missing reference otherwise.
+///
+/// # Errors
+///
+/// - If the timeline can't be serialized.
+pub fn write(
+    name: &str,
+    scene_changes: &[usize],
+    frame_count: usize,
+    fps: f64,
+    target_url: Option<&str>,
+) -> anyhow::Result<String> {
+    let time = |value: usize| {
+        json!({
+            "OTIO_SCHEMA": "RationalTime.1",
+            "rate": fps,
+            "value": value as f64,
+        })
+    };
+    let media_reference = target_url.map_or_else(
+        || {
+            json!({
+                "OTIO_SCHEMA": "MissingReference.1",
+                "metadata": {},
+                "name": "",
+                "available_range": null,
+            })
+        },
+        |target_url| {
+            json!({
+                "OTIO_SCHEMA": "ExternalReference.1",
+                "metadata": {},
+                "name": "",
+                "available_range": {
+                    "OTIO_SCHEMA": "TimeRange.1",
+                    "start_time": time(0),
+                    "duration": time(frame_count),
+                },
+                "target_url": target_url,
+            })
+        },
+    );
+    let clips = scene_changes
+        .iter()
+        .enumerate()
+        .map(|(i, &start)| {
+            let end = scene_changes.get(i + 1).copied().unwrap_or(frame_count);
+            json!({
+                "OTIO_SCHEMA": "Clip.2",
+                "metadata": {},
+                "name": format!("Scene {}", i + 1),
+                "source_range": {
+                    "OTIO_SCHEMA": "TimeRange.1",
+                    "start_time": time(start),
+                    "duration": time(end - start),
+                },
+                "effects": [],
+                "markers": [],
+                "enabled": true,
+                "media_references": { "DEFAULT_MEDIA": media_reference },
+                "active_media_reference_key": "DEFAULT_MEDIA",
+            })
+        })
+        .collect::<Vec<_>>();
+    let timeline = json!({
+        "OTIO_SCHEMA": "Timeline.1",
+        "metadata": {},
+        "name": name,
+        "global_start_time": null,
+        "tracks": {
+            "OTIO_SCHEMA": "Stack.1",
+            "metadata": {},
+            "name": "tracks",
+            "source_range": null,
+            "effects": [],
+            "markers": [],
+            "enabled": true,
+            "children": [{
+                "OTIO_SCHEMA": "Track.1",
+                "metadata": {},
+                "name": "Video",
+                "kind": "Video",
+                "source_range": null,
+                "effects": [],
+                "markers": [],
+                "enabled": true,
+                "children": clips,
+            }],
+        },
+    });
+    Ok(serde_json::to_string_pretty(&timeline)?)
+}
+
+/// Returns the frame numbers where each clip on the first video track
+/// of `otio` starts, relative to the start of the track and in ascending order.
+///
+/// `fps` is used to convert times to frame numbers,
+/// defaulting to the rate of the first clip.
+///
+/// # Errors
+///
+/// - If `otio` is not a timeline or its first video track has no clips.
+/// - If a clip has no duration, either from its source range or from the
+///   available range of its media.
+pub fn parse(otio: &str, fps: Option<f64>) -> anyhow::Result<Vec<usize>> {
+    let timeline: Value = serde_json::from_str(otio).context("Invalid OTIO file")?;
+    let track = timeline["tracks"]["children"]
+        .as_array()
+        .and_then(|tracks| tracks.iter().find(|track| track["kind"] == "Video"))
+        .context("No video track found")?;
+    let items = track["children"]
+        .as_array()
+        .context("Invalid video track")?;
+
+    // Gaps move the following clips along the track, transitions don't
+    let mut position = 0.0;
+    let mut starts = Vec::new();
+    let mut fps = fps;
+    for item in items {
+        let schema = item["OTIO_SCHEMA"].as_str().unwrap_or_default();
+        if schema.starts_with("Transition.") {
+            continue;
+        }
+        let is_clip = schema.starts_with("Clip.");
+        let range = if is_clip && item["source_range"].is_null() {
+            // A clip without a source range uses all of its media
+            available_range(item).with_context(|| {
+                format!(
+                    "Clip {} has no source range or available range",
+                    item["name"]
+                )
+            })?
+        } else {
+            &item["source_range"]
+        };
+        let duration = &range["duration"];
+        let (Some(value), Some(rate)) = (duration["value"].as_f64(), duration["rate"].as_f64())
+        else {
+            ensure!(!is_clip, "Clip {} has an invalid duration", item["name"]);
+            continue;
+        };
+        if is_clip {
+            starts.push(position);
+            fps.get_or_insert(rate);
+        }
+        position += value / rate;
+    }
+    ensure!(!starts.is_empty(), "No clips found on the video track");
+
+    let fps = fps.unwrap_or_default();
+    let mut frames = starts
+        .into_iter()
+        .map(|seconds| (seconds * fps).round() as usize)
+        .collect::<Vec<_>>();
+    frames.dedup();
+    let start = frames[0];
+    Ok(frames.into_iter().map(|frame| frame - start).collect())
+}
+
+/// The available range of the active media reference of `clip`.
+fn available_range(clip: &Value) -> Option<&Value> {
+    let reference = clip["active_media_reference_key"]
+        .as_str()
+        .map_or(&clip["media_reference"], |key| {
+            &clip["media_references"][key]
+        });
+    Some(&reference["available_range"]).filter(|range| !range.is_null())
+}
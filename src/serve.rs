@@ -0,0 +1,234 @@
+//! A long-lived job server for running many detection passes
+//! without paying process startup costs for each one.
+//!
+//! Jobs are read as one JSON object per line, and one JSON response
+//! is written per line in the same order. Because the process stays alive
+//! between jobs, rav1e's thread pool and this module's line buffers
+//! are reused across all jobs.
+
+use std::{
+    fs::File,
+    io::{BufRead, BufReader, Read, Write},
+    panic::{self, AssertUnwindSafe},
+    path::PathBuf,
+};
+#[cfg(feature = "ffmpeg")]
+use std::{io, path::Path};
+
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "ffmpeg")]
+use crate::ffmpeg::{FfmpegDecoder, FfmpegOptions};
+use crate::{
+    decoder::Decoder, detect_scene_changes_with_overrides, DetectionOptions, DetectionResults,
+    FrameOverrides,
+};
+
+/// A single detection job.
+///
+/// Unknown fields, here or in the nested options, are rejected
+/// so that typos are reported instead of silently using defaults.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Job {
+    /// An identifier echoed back in the response,
+    /// so clients can match responses to jobs.
+    #[serde(default)]
+    pub id: Option<String>,
+    /// The file to analyze. y4m files are read directly. Other files are
+    /// decoded with ffmpeg if the `ffmpeg` feature is enabled, using the
+    /// default stream selection of `FfmpegOptions`,
+    /// and are otherwise rejected.
+    pub input: PathBuf,
+    /// Options for this job. Unset fields use their defaults.
+    #[serde(default)]
+    pub options: DetectionOptions,
+    /// Forced cuts, forbidden ranges and zones for this job.
+    #[serde(default)]
+    pub overrides: FrameOverrides,
+    /// Stop analyzing after this many frames.
+    #[serde(default)]
+    pub frame_limit: Option<usize>,
+}
+
+/// The response written for each job.
+#[derive(Debug, Clone, Serialize)]
+pub struct JobResponse {
+    /// The `id` of the job this responds to.
+    pub id: Option<String>,
+    /// The results, if the job succeeded.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub results: Option<DetectionResults>,
+    /// A description of the failure, if the job failed.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Runs a single job to completion.
+///
+/// # Errors
+///
+/// - If the input file cannot be opened or decoded.
+/// - If a frame cannot be read.
+pub fn run_job(job: &Job) -> anyhow::Result<DetectionResults> {
+    #[cfg(feature = "ffmpeg")]
+    if !is_y4m(&job.input) {
+        let dec = FfmpegDecoder::new(&job.input, &FfmpegOptions::default())?;
+        return detect(&mut Decoder::<io::Empty>::Ffmpeg(dec), job);
+    }
+    let mut reader = BufReader::new(File::open(&job.input)?);
+    detect(&mut Decoder::Y4m(y4m::Decoder::new(&mut reader)?), job)
+}
+
+/// Whether `path` has a `.y4m` extension.
+#[cfg(feature = "ffmpeg")]
+fn is_y4m(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("y4m"))
+}
+
+fn detect<R: Read>(dec: &mut Decoder<R>, job: &Job) -> anyhow::Result<DetectionResults> {
+    let bit_depth = dec.get_video_details()?.bit_depth;
+    if bit_depth == 8 {
+        detect_scene_changes_with_overrides::<_, u8>(
+            dec,
+            job.options,
+            &job.overrides,
+            job.frame_limit,
+            None,
+        )
+    } else {
+        detect_scene_changes_with_overrides::<_, u16>(
+            dec,
+            job.options,
+            &job.overrides,
+            job.frame_limit,
+            None,
+        )
+    }
+}
+
+/// Reads jobs line by line from `reader` and writes one response line
+/// per job to `writer`, until `reader` reaches end of input.
+///
+/// Blank lines are ignored. A line that fails to parse or a job that fails,
+/// including one that panics, produces a response with `error` set;
+/// it does not stop the server.
+///
+/// # Errors
+///
+/// - If reading from `reader` or writing to `writer` fails.
+pub fn serve<R: BufRead, W: Write>(mut reader: R, mut writer: W) -> anyhow::Result<()> {
+    let mut line = String::new();
+    let mut output = Vec::new();
+    loop {
+        line.clear();
+        if reader.read_line(&mut line)? == 0 {
+            return Ok(());
+        }
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let response = match serde_json::from_str::<Job>(&line) {
+            Ok(job) => match catch_panic(&job) {
+                Ok(results) => JobResponse {
+                    id: job.id,
+                    results: Some(results),
+                    error: None,
+                },
+                Err(e) => JobResponse {
+                    id: job.id,
+                    results: None,
+                    error: Some(format!("{e:#}")),
+                },
+            },
+            Err(e) => JobResponse {
+                id: None,
+                results: None,
+                error: Some(format!("Invalid job: {e}")),
+            },
+        };
+
+        write_response(&mut writer, &mut output, &response)?;
+    }
+}
+
+/// Runs `job`, turning a panic into an error so one job can't stop the server.
+fn catch_panic(job: &Job) -> anyhow::Result<DetectionResults> {
+    panic::catch_unwind(AssertUnwindSafe(|| run_job(job))).unwrap_or_else(|payload| {
+        let message = payload
+            .downcast_ref::<&str>()
+            .copied()
+            .or_else(|| payload.downcast_ref::<String>().map(String::as_str))
+            .unwrap_or("unknown cause");
+        Err(anyhow::anyhow!("Job panicked: {message}"))
+    })
+}
+
+/// Writes `response` as a single line, using `output` as a buffer.
+fn write_response<W: Write>(
+    writer: &mut W,
+    output: &mut Vec<u8>,
+    response: &JobResponse,
+) -> anyhow::Result<()> {
+    output.clear();
+    serde_json::to_writer(&mut *output, response)?;
+    output.push(b'\n');
+    writer.write_all(output)?;
+    writer.flush()?;
+    Ok(())
+}
+
+/// Writes a response with no `id` reporting `error`, for failures
+/// that aren't tied to a job.
+#[cfg(unix)]
+fn write_error<W: Write>(mut writer: W, error: &anyhow::Error) -> anyhow::Result<()> {
+    let response = JobResponse {
+        id: None,
+        results: None,
+        error: Some(format!("{error:#}")),
+    };
+    write_response(&mut writer, &mut Vec::new(), &response)
+}
+
+/// Listens on a Unix socket at `path`, serving each connection
+/// with [`serve`] on its own thread.
+///
+/// A stale socket left at `path` by a previous server is replaced.
+/// If a connection fails to be read from, it is sent a response with
+/// `error` set, like one for a failed job, and closed.
+///
+/// # Errors
+///
+/// - If the socket cannot be bound.
+/// - If a connection cannot be accepted.
+#[cfg(unix)]
+pub fn serve_unix_socket<P: AsRef<std::path::Path>>(path: P) -> anyhow::Result<()> {
+    use std::{
+        os::unix::{fs::FileTypeExt, net::UnixListener},
+        thread,
+    };
+
+    // Clean up a socket left behind by a previous server,
+    // but never remove anything that is not a socket
+    if std::fs::symlink_metadata(&path).is_ok_and(|meta| meta.file_type().is_socket()) {
+        std::fs::remove_file(&path)?;
+    }
+    let listener = UnixListener::bind(&path)?;
+    for stream in listener.incoming() {
+        let stream = stream?;
+        thread::spawn(move || {
+            let result = stream
+                .try_clone()
+                .map_err(anyhow::Error::from)
+                .and_then(|reader| serve(BufReader::new(reader), &stream));
+            if let Err(e) = result {
+                // The client may also have disconnected,
+                // in which case there is nobody left to tell
+                let _ = write_error(&stream, &e.context("Failed to read from connection"));
+            }
+        });
+    }
+    Ok(())
+}
@@ -0,0 +1,278 @@
+//! Generating synthetic y4m clips with known scene changes.
+//!
+//! These are useful for testing detection settings,
+//! since the expected scene changes are known exactly.
+
+use std::io::Write;
+
+use anyhow::bail;
+use rav1e::prelude::ChromaSampling;
+
+/// A run of frames in a [`SyntheticClip`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Segment {
+    /// A new scene with slow motion, starting with a scene change.
+    Scene { frames: usize },
+    /// A new scene with no motion or noise at all, starting with a scene
+    /// change.
+    Static { frames: usize },
+    /// Frames of the current scene washed out towards white,
+    /// which are not scene changes.
+    /// Usually followed by [`Segment::Continue`].
+    Flash { frames: usize },
+    /// More frames of the current scene, without a scene change.
+    Continue { frames: usize },
+    /// The current scene fading nearly to black, which is not a scene change.
+    FadeOut { frames: usize },
+}
+
+impl Segment {
+    const fn frames(self) -> usize {
+        match self {
+            Segment::Scene { frames }
+            | Segment::Static { frames }
+            | Segment::Flash { frames }
+            | Segment::Continue { frames }
+            | Segment::FadeOut { frames } => frames,
+        }
+    }
+
+    const fn starts_scene(self) -> bool {
+        matches!(self, Segment::Scene { .. } | Segment::Static { .. })
+    }
+}
+
+/// A description of a synthetic clip.
+#[derive(Debug, Clone)]
+pub struct SyntheticClip {
+    pub width: usize,
+    pub height: usize,
+    /// 8, 10 or 12. 10-bit is not supported with `ChromaSampling::Cs400`.
+    pub bit_depth: usize,
+    pub chroma_sampling: ChromaSampling,
+    pub segments: Vec<Segment>,
+}
+
+impl Default for SyntheticClip {
+    fn default() -> Self {
+        SyntheticClip {
+            width: 128,
+            height: 96,
+            bit_depth: 8,
+            chroma_sampling: ChromaSampling::Cs420,
+            segments: Vec::new(),
+        }
+    }
+}
+
+/// Parameters of each generated scene, in 8-bit values:
+/// luma base, luma amplitude, pattern period, U, V
+const SCENES: [(u16, u16, usize, u16, u16); 6] = [
+    (50, 30, 64, 90, 160),
+    (180, 40, 48, 160, 90),
+    (110, 60, 96, 128, 200),
+    (220, 20, 32, 200, 128),
+    (30, 20, 80, 100, 100),
+    (150, 50, 56, 150, 150),
+];
+
+/// The luma value flash frames are blended towards
+const FLASH_LUMA: u16 = 235;
+/// How strongly flash frames are blended, out of 256
+const FLASH_WEIGHT: u16 = 192;
+
+/// Blends a frame towards a flat colour, with neutral chroma
+#[derive(Clone, Copy)]
+struct Blend {
+    luma: u16,
+    /// Out of 256
+    weight: u16,
+}
+
+impl Blend {
+    const NONE: Blend = Blend { luma: 0, weight: 0 };
+
+    const fn apply(self, value: u16, target: u16) -> u16 {
+        (value * (256 - self.weight) + target * self.weight) / 256
+    }
+}
+
+/// The state of the current scene while generating frames
+#[derive(Clone, Copy)]
+struct SceneState {
+    index: usize,
+    /// Frames since the start of the scene, used for motion
+    time: usize,
+    moving: bool,
+}
+
+impl SyntheticClip {
+    /// The total number of frames in the clip.
+    #[must_use]
+    pub fn frame_count(&self) -> usize {
+        self.segments.iter().map(|segment| segment.frames()).sum()
+    }
+
+    /// The frames a detector should report as scene changes,
+    /// including frame 0.
+    #[must_use]
+    pub fn scene_changes(&self) -> Vec<usize> {
+        let mut scene_changes = vec![0];
+        let mut frameno = 0;
+        for segment in &self.segments {
+            if segment.starts_scene() && frameno > 0 {
+                scene_changes.push(frameno);
+            }
+            frameno += segment.frames();
+        }
+        scene_changes
+    }
+
+    /// Writes the clip to `writer` as a y4m stream.
+    ///
+    /// # Errors
+    ///
+    /// - If the bit depth and chroma sampling cannot be represented in y4m.
+    /// - If writing fails.
+    pub fn write_y4m<W: Write>(&self, writer: W) -> anyhow::Result<()> {
+        let colorspace = self.colorspace()?;
+        let mut encoder = y4m::encode(self.width, self.height, y4m::Ratio::new(30, 1))
+            .with_colorspace(colorspace)
+            .write_header(writer)?;
+
+        let mut scene = SceneState {
+            index: 0,
+            time: 0,
+            moving: true,
+        };
+        let mut noise = Noise(0x2545_f491);
+        let mut scenes_started = 0;
+        for segment in &self.segments {
+            if segment.starts_scene() {
+                scene = SceneState {
+                    index: scenes_started % SCENES.len(),
+                    time: 0,
+                    moving: matches!(segment, Segment::Scene { .. }),
+                };
+                scenes_started += 1;
+            }
+            for i in 0..segment.frames() {
+                let blend = match *segment {
+                    Segment::Flash { .. } => Blend {
+                        luma: FLASH_LUMA,
+                        weight: FLASH_WEIGHT,
+                    },
+                    // Fade towards black, stopping one step short of it
+                    Segment::FadeOut { frames } => Blend {
+                        luma: 0,
+                        weight: ((i + 1) * 256 / (frames + 1)) as u16,
+                    },
+                    _ => Blend::NONE,
+                };
+                let (luma, u, v) = self.scene_planes(scene, blend, &mut noise);
+                encoder.write_frame(&y4m::Frame::new([&luma, &u, &v], None))?;
+                scene.time += 1;
+            }
+        }
+        Ok(())
+    }
+
+    /// Generates the clip as an in-memory y4m stream.
+    ///
+    /// # Errors
+    ///
+    /// - If the bit depth and chroma sampling cannot be represented in y4m.
+    pub fn to_y4m(&self) -> anyhow::Result<Vec<u8>> {
+        let mut data = Vec::new();
+        self.write_y4m(&mut data)?;
+        Ok(data)
+    }
+
+    fn colorspace(&self) -> anyhow::Result<y4m::Colorspace> {
+        use y4m::Colorspace::{
+            C420p10, C420p12, C422p10, C422p12, C444p10, C444p12, Cmono, Cmono12, C420, C422, C444,
+        };
+        use ChromaSampling::{Cs400, Cs420, Cs422, Cs444};
+        Ok(match (self.chroma_sampling, self.bit_depth) {
+            (Cs420, 8) => C420,
+            (Cs420, 10) => C420p10,
+            (Cs420, 12) => C420p12,
+            (Cs422, 8) => C422,
+            (Cs422, 10) => C422p10,
+            (Cs422, 12) => C422p12,
+            (Cs444, 8) => C444,
+            (Cs444, 10) => C444p10,
+            (Cs444, 12) => C444p12,
+            (Cs400, 8) => Cmono,
+            (Cs400, 12) => Cmono12,
+            (chroma_sampling, bit_depth) => {
+                bail!("{bit_depth}-bit {chroma_sampling:?} is not supported by y4m")
+            }
+        })
+    }
+
+    /// Generates the planes of a frame of `scene`, blended with `blend`.
+    fn scene_planes(
+        &self,
+        scene: SceneState,
+        blend: Blend,
+        noise: &mut Noise,
+    ) -> (Vec<u8>, Vec<u8>, Vec<u8>) {
+        let (base, amplitude, period, u, v) = SCENES[scene.index];
+        let offset = if scene.moving { scene.time } else { 0 };
+
+        let mut luma = Vec::with_capacity(self.width * self.height * 2);
+        for y in 0..self.height {
+            for x in 0..self.width {
+                // A diagonal triangle wave, moving one pixel per frame
+                let phase = (x + y + offset) % period;
+                let distance = phase.abs_diff(period / 2) as u16;
+                let mut value = base - amplitude / 2 + distance * amplitude * 2 / period as u16;
+                if scene.moving {
+                    value = value.saturating_add_signed(noise.next());
+                }
+                self.push_sample(&mut luma, blend.apply(value, blend.luma));
+            }
+        }
+
+        let (u, v) = self.flat_chroma(blend.apply(u, 128), blend.apply(v, 128));
+        (luma, u, v)
+    }
+
+    fn flat_chroma(&self, u: u16, v: u16) -> (Vec<u8>, Vec<u8>) {
+        let (chroma_width, chroma_height) = self
+            .chroma_sampling
+            .get_chroma_dimensions(self.width, self.height);
+        let mut u_plane = Vec::with_capacity(chroma_width * chroma_height * 2);
+        let mut v_plane = Vec::with_capacity(chroma_width * chroma_height * 2);
+        for _ in 0..chroma_width * chroma_height {
+            self.push_sample(&mut u_plane, u);
+            self.push_sample(&mut v_plane, v);
+        }
+        (u_plane, v_plane)
+    }
+
+    /// Appends an 8-bit `value` to `plane`, scaled to the clip's bit depth.
+    fn push_sample(&self, plane: &mut Vec<u8>, value: u16) {
+        let value = value.min(255) << (self.bit_depth - 8);
+        if self.bit_depth > 8 {
+            plane.extend_from_slice(&value.to_le_bytes());
+        } else {
+            plane.push(value as u8);
+        }
+    }
+}
+
+/// A small deterministic noise generator, so that clips are reproducible
+struct Noise(u32);
+
+impl Noise {
+    /// Returns a value between -2 and 2.
+    fn next(&mut self) -> i16 {
+        // xorshift32
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 17;
+        self.0 ^= self.0 << 5;
+        (self.0 % 5) as i16 - 2
+    }
+}
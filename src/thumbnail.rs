@@ -0,0 +1,404 @@
+//! Extracting an image of each scene from the frames decoded during detection.
+//!
+//! Requires the `image` feature.
+
+use std::{collections::VecDeque, io::Read, path::Path};
+
+use anyhow::{bail, ensure};
+use image::{imageops, Rgb, RgbImage};
+use rav1e::prelude::{CastFromPrimitive, ChromaSampling, Frame, Pixel};
+
+use crate::{
+    analyze::sharpness,
+    decoder::{Decoder, VideoDetails},
+    detect_scene_changes_with, DetectionOptions, DetectionResults, FrameOverrides,
+};
+
+/// The most frames of a scene kept as candidates for `ThumbnailFrame::Middle`.
+/// Scenes too long to keep every frame of use the closest frame kept.
+const MAX_MIDDLE_CANDIDATES: usize = 16;
+
+/// Which frame of each scene to use as its thumbnail.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+pub enum ThumbnailFrame {
+    /// The first frame of the scene, where the scene change was detected.
+    First,
+    /// The frame halfway through the scene.
+    Middle,
+    /// The frame with the most detail, which avoids motion blur
+    /// and frames in the middle of a transition.
+    Sharpest,
+}
+
+/// Options determining how to extract thumbnails.
+#[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serialize", serde(default))]
+pub struct ThumbnailOptions {
+    /// Which frame of each scene to use.
+    pub frame: ThumbnailFrame,
+    /// Scale thumbnails down to at most this width, keeping the aspect ratio.
+    /// `None` keeps the size of the clip.
+    pub max_width: Option<u32>,
+}
+
+impl Default for ThumbnailOptions {
+    fn default() -> Self {
+        ThumbnailOptions {
+            frame: ThumbnailFrame::Middle,
+            max_width: Some(480),
+        }
+    }
+}
+
+/// The thumbnail of a scene.
+#[derive(Debug, Clone)]
+pub struct Thumbnail {
+    /// The frame number of the scene change starting the scene.
+    pub scene_start: usize,
+    /// The frame number the thumbnail was taken from.
+    pub frameno: usize,
+    pub image: RgbImage,
+}
+
+impl Thumbnail {
+    /// Writes the thumbnail to `path`, as PNG or JPEG depending on its extension.
+    ///
+    /// # Errors
+    ///
+    /// - If the extension is not a supported image format, or writing fails.
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> anyhow::Result<()> {
+        Ok(self.image.save(path)?)
+    }
+}
+
+/// Runs through a video clip like [`crate::detect_scene_changes_with_overrides`],
+/// also returning a thumbnail of each scene taken from the decoded frames,
+/// so the clip does not need to be decoded twice.
+///
+/// # Errors
+///
+/// - If using a Vapoursynth script that contains an unsupported video format.
+/// - If a frame cannot be decoded.
+/// - If `opts` sets `max_scene_length` or `target_scene_count`,
+///   which add scene changes only after the whole clip has been read.
+/// - If `opts.lookahead_distance` is 0 or above
+///   [`MAX_LOOKAHEAD_DISTANCE`](crate::MAX_LOOKAHEAD_DISTANCE).
+pub fn detect_with_thumbnails<R: Read, T: Pixel>(
+    dec: &mut Decoder<R>,
+    opts: DetectionOptions,
+    overrides: &FrameOverrides,
+    thumbnail_opts: ThumbnailOptions,
+    frame_limit: Option<usize>,
+    progress_callback: Option<&dyn Fn(usize, usize)>,
+) -> anyhow::Result<(DetectionResults, Vec<Thumbnail>)> {
+    if opts.max_scene_length.is_some() || opts.target_scene_count.is_some() {
+        bail!("Thumbnails can't be extracted with max_scene_length or target_scene_count");
+    }
+    let video_details = dec.get_video_details()?;
+    let mut collector = Collector::new(video_details, thumbnail_opts);
+    let results = detect_scene_changes_with::<R, T>(
+        dec,
+        opts,
+        overrides,
+        frame_limit,
+        progress_callback,
+        &mut |frameno, frame: &Frame<T>, scene_change| collector.push(frameno, frame, scene_change),
+    )?;
+    let thumbnails = collector.finish(&results.scene_changes, results.frame_count);
+    Ok((results, thumbnails))
+}
+
+/// Converts the visible area of `frame` to 8-bit RGB.
+///
+/// Frames are assumed to be limited range, using the BT.709 matrix
+/// for HD clips and the BT.601 matrix otherwise,
+/// since the decoders don't report which one the clip uses.
+#[must_use]
+pub fn frame_to_rgb<T: Pixel>(frame: &Frame<T>, video_details: &VideoDetails) -> RgbImage {
+    let (kr, kb): (f32, f32) = if video_details.width >= 1280 || video_details.height >= 720 {
+        (0.2126, 0.0722)
+    } else {
+        (0.299, 0.114)
+    };
+    let kg = 1.0 - kr - kb;
+    let (r_v, b_u) = (2.0 * (1.0 - kr), 2.0 * (1.0 - kb));
+    let (g_u, g_v) = (b_u * kb / kg, r_v * kr / kg);
+    let scale = f32::from(1u16 << (video_details.bit_depth - 8));
+    let has_chroma = video_details.chroma_sampling != ChromaSampling::Cs400;
+    let (xdec, ydec) = if has_chroma {
+        (frame.planes[1].cfg.xdec, frame.planes[1].cfg.ydec)
+    } else {
+        (0, 0)
+    };
+
+    let mut image = RgbImage::new(video_details.width as u32, video_details.height as u32);
+    for y in 0..video_details.height {
+        let luma_row = frame.planes[0].row(y as isize);
+        let chroma_rows = has_chroma.then(|| {
+            (
+                frame.planes[1].row((y >> ydec) as isize),
+                frame.planes[2].row((y >> ydec) as isize),
+            )
+        });
+        for x in 0..video_details.width {
+            let luma = (u32::cast_from(luma_row[x]) as f32 / scale - 16.0) * (255.0 / 219.0);
+            let (u, v) = chroma_rows.map_or((0.0, 0.0), |(u_row, v_row)| {
+                (
+                    (u32::cast_from(u_row[x >> xdec]) as f32 / scale - 128.0) * (255.0 / 224.0),
+                    (u32::cast_from(v_row[x >> xdec]) as f32 / scale - 128.0) * (255.0 / 224.0),
+                )
+            });
+            let r = r_v.mul_add(v, luma);
+            let g = g_v.mul_add(-v, g_u.mul_add(-u, luma));
+            let b = b_u.mul_add(u, luma);
+            let to_u8 = |value: f32| value.round().clamp(0.0, 255.0) as u8;
+            image.put_pixel(x as u32, y as u32, Rgb([to_u8(r), to_u8(g), to_u8(b)]));
+        }
+    }
+    image
+}
+
+/// Arranges `thumbnails` in a grid with `columns` columns,
+/// each scaled to `tile_width` pixels wide.
+///
+/// # Errors
+///
+/// - If `thumbnails` is empty, or `columns` or `tile_width` is 0.
+pub fn contact_sheet(
+    thumbnails: &[Thumbnail],
+    columns: usize,
+    tile_width: u32,
+) -> anyhow::Result<RgbImage> {
+    ensure!(
+        !thumbnails.is_empty(),
+        "No thumbnails to make a contact sheet of"
+    );
+    ensure!(
+        columns > 0 && tile_width > 0,
+        "A contact sheet needs at least one column of non-zero width"
+    );
+    let tile_height = thumbnails
+        .iter()
+        .map(|thumbnail| scaled_height(&thumbnail.image, tile_width))
+        .max()
+        .unwrap_or_default();
+    let columns = columns.min(thumbnails.len());
+    let rows = thumbnails.len().div_ceil(columns);
+
+    let mut sheet = RgbImage::new(tile_width * columns as u32, tile_height * rows as u32);
+    for (i, thumbnail) in thumbnails.iter().enumerate() {
+        let tile = imageops::resize(
+            &thumbnail.image,
+            tile_width,
+            scaled_height(&thumbnail.image, tile_width),
+            imageops::FilterType::Triangle,
+        );
+        imageops::replace(
+            &mut sheet,
+            &tile,
+            i64::from(tile_width) * (i % columns) as i64,
+            i64::from(tile_height) * (i / columns) as i64,
+        );
+    }
+    Ok(sheet)
+}
+
+/// The height of `image` scaled to `width`, keeping its aspect ratio.
+fn scaled_height(image: &RgbImage, width: u32) -> u32 {
+    let height = u64::from(image.height()) * u64::from(width) / u64::from(image.width().max(1));
+    (height as u32).max(1)
+}
+
+/// A frame that may become a thumbnail.
+struct Candidate {
+    frameno: usize,
+    sharpness: f64,
+    image: RgbImage,
+}
+
+/// Converts `frame` to a candidate, scaled down to `max_width`.
+fn candidate<T: Pixel>(
+    video_details: &VideoDetails,
+    max_width: Option<u32>,
+    frameno: usize,
+    frame: &Frame<T>,
+    sharpness: f64,
+) -> Candidate {
+    let mut image = frame_to_rgb(frame, video_details);
+    if let Some(max_width) = max_width {
+        if image.width() > max_width && max_width > 0 {
+            let height = scaled_height(&image, max_width);
+            image = imageops::resize(&image, max_width, height, imageops::FilterType::Triangle);
+        }
+    }
+    Candidate {
+        frameno,
+        sharpness,
+        image,
+    }
+}
+
+/// The candidates of the scene currently being read.
+struct Scene {
+    start: usize,
+    length: usize,
+    /// The chosen frame so far for `ThumbnailFrame::First` and `Sharpest`.
+    best: Option<Candidate>,
+    /// Frames that may still end up closest to the middle of the scene,
+    /// for `ThumbnailFrame::Middle`.
+    middle: VecDeque<Candidate>,
+    /// Only every `step`th frame of the scene is kept in `middle`.
+    step: usize,
+}
+
+/// Picks a thumbnail for each scene as frames are decoded,
+/// keeping only the frames that may still be picked.
+struct Collector {
+    video_details: VideoDetails,
+    opts: ThumbnailOptions,
+    current: Option<Scene>,
+    /// The thumbnails of scenes that have ended.
+    scenes: Vec<Candidate>,
+}
+
+impl Collector {
+    fn new(video_details: VideoDetails, opts: ThumbnailOptions) -> Self {
+        Self {
+            video_details,
+            opts,
+            current: None,
+            scenes: Vec::new(),
+        }
+    }
+
+    fn push<T: Pixel>(&mut self, frameno: usize, frame: &Frame<T>, scene_change: bool) {
+        if scene_change || self.current.is_none() {
+            self.end_scene();
+            self.current = Some(Scene {
+                start: frameno,
+                length: 0,
+                best: None,
+                middle: VecDeque::new(),
+                step: 1,
+            });
+        }
+        let Some(scene) = self.current.as_mut() else {
+            return;
+        };
+        let offset = scene.length;
+        scene.length += 1;
+
+        match self.opts.frame {
+            ThumbnailFrame::First => {
+                if offset == 0 {
+                    scene.best = Some(candidate(
+                        &self.video_details,
+                        self.opts.max_width,
+                        frameno,
+                        frame,
+                        0.0,
+                    ));
+                }
+            }
+            ThumbnailFrame::Sharpest => {
+                let sharpness = sharpness(frame, &self.video_details);
+                if scene
+                    .best
+                    .as_ref()
+                    .is_none_or(|best| sharpness > best.sharpness)
+                {
+                    scene.best = Some(candidate(
+                        &self.video_details,
+                        self.opts.max_width,
+                        frameno,
+                        frame,
+                        sharpness,
+                    ));
+                }
+            }
+            ThumbnailFrame::Middle => {
+                if offset % scene.step == 0 {
+                    scene.middle.push_back(candidate(
+                        &self.video_details,
+                        self.opts.max_width,
+                        frameno,
+                        frame,
+                        0.0,
+                    ));
+                }
+                // The middle only moves forwards as the scene gets longer,
+                // so frames before the kept frame closest to it aren't needed again
+                let middle = scene.start + scene.length / 2;
+                while scene
+                    .middle
+                    .get(1)
+                    .is_some_and(|next| next.frameno <= middle)
+                {
+                    scene.middle.pop_front();
+                }
+                if scene.middle.len() > MAX_MIDDLE_CANDIDATES {
+                    scene.step *= 2;
+                    let (start, step) = (scene.start, scene.step);
+                    scene
+                        .middle
+                        .retain(|candidate| (candidate.frameno - start) % step == 0);
+                }
+            }
+        }
+    }
+
+    fn end_scene(&mut self) {
+        let Some(scene) = self.current.take() else {
+            return;
+        };
+        let middle = scene.start + scene.length / 2;
+        let chosen = match self.opts.frame {
+            ThumbnailFrame::First | ThumbnailFrame::Sharpest => scene.best,
+            ThumbnailFrame::Middle => scene
+                .middle
+                .into_iter()
+                .min_by_key(|candidate| candidate.frameno.abs_diff(middle)),
+        };
+        if let Some(chosen) = chosen {
+            self.scenes.push(chosen);
+        }
+    }
+
+    /// Returns a thumbnail for each of `scene_changes`.
+    ///
+    /// Post-processing may have merged scenes read during detection,
+    /// in which case the merged scene uses the first or sharpest of their
+    /// thumbnails, or for `ThumbnailFrame::Middle` the one closest to its middle.
+    fn finish(mut self, scene_changes: &[usize], frame_count: usize) -> Vec<Thumbnail> {
+        self.end_scene();
+        let mut scenes = self.scenes.into_iter().peekable();
+        let mut thumbnails = Vec::with_capacity(scene_changes.len());
+        for (i, &start) in scene_changes.iter().enumerate() {
+            let end = scene_changes.get(i + 1).copied().unwrap_or(frame_count);
+            let middle = start + (end - start) / 2;
+            let mut chosen: Option<Candidate> = None;
+            while let Some(candidate) = scenes.next_if(|candidate| candidate.frameno < end) {
+                let better = chosen.as_ref().is_none_or(|chosen| match self.opts.frame {
+                    ThumbnailFrame::First => false,
+                    ThumbnailFrame::Sharpest => candidate.sharpness > chosen.sharpness,
+                    ThumbnailFrame::Middle => {
+                        candidate.frameno.abs_diff(middle) < chosen.frameno.abs_diff(middle)
+                    }
+                });
+                if better {
+                    chosen = Some(candidate);
+                }
+            }
+            if let Some(chosen) = chosen {
+                thumbnails.push(Thumbnail {
+                    scene_start: start,
+                    frameno: chosen.frameno,
+                    image: chosen.image,
+                });
+            }
+        }
+        thumbnails
+    }
+}
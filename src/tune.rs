@@ -0,0 +1,115 @@
+//! Searching for the `DetectionOptions` that best match a ground truth.
+//!
+//! Clips are only decoded once, by [`crate::score_frames`],
+//! after which each combination of options is decided from the cached scores.
+
+use crate::{
+    eval::{evaluate, Evaluation},
+    Algorithm, DetectionOptions, FrameScores, SceneDetectionSpeed,
+};
+
+/// The values of each option to try in [`sweep`].
+/// Every combination of these values is tried.
+#[derive(Debug, Clone)]
+pub struct SweepRanges {
+    pub algorithms: Vec<Algorithm>,
+    pub threshold_scales: Vec<f64>,
+    pub min_scenecut_distances: Vec<Option<usize>>,
+    pub max_scenecut_distances: Vec<Option<usize>>,
+    /// Lookahead distances to try with flash detection enabled.
+    /// `None` tries disabling flash detection.
+    pub lookahead_distances: Vec<Option<usize>>,
+}
+
+impl Default for SweepRanges {
+    fn default() -> Self {
+        SweepRanges {
+            algorithms: vec![
+                Algorithm::PixelDifference,
+                Algorithm::Histogram,
+                Algorithm::Adaptive,
+                Algorithm::Hsv,
+            ],
+            threshold_scales: (5..=20).map(|scale| f64::from(scale) / 10.0).collect(),
+            min_scenecut_distances: vec![None, Some(6), Some(12), Some(24)],
+            max_scenecut_distances: vec![None, Some(60), Some(120), Some(240)],
+            lookahead_distances: vec![None, Some(1), Some(2), Some(3), Some(4), Some(5)],
+        }
+    }
+}
+
+impl SweepRanges {
+    /// Every combination of options in these ranges, applied on top of `base`.
+    fn combinations(&self, base: DetectionOptions) -> Vec<DetectionOptions> {
+        let mut combinations = Vec::new();
+        for &algorithm in &self.algorithms {
+            for &threshold_scale in &self.threshold_scales {
+                for &min_scenecut_distance in &self.min_scenecut_distances {
+                    for &max_scenecut_distance in &self.max_scenecut_distances {
+                        for &lookahead_distance in &self.lookahead_distances {
+                            combinations.push(DetectionOptions {
+                                analysis_speed: SceneDetectionSpeed::Fast,
+                                algorithm,
+                                ensemble: None,
+                                threshold_scale,
+                                min_scenecut_distance,
+                                max_scenecut_distance,
+                                detect_flashes: lookahead_distance.is_some(),
+                                lookahead_distance: lookahead_distance
+                                    .unwrap_or(base.lookahead_distance),
+                                ..base
+                            });
+                        }
+                    }
+                }
+            }
+        }
+        combinations
+    }
+}
+
+/// The result of trying one combination of options.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize))]
+pub struct TuneResult {
+    pub options: DetectionOptions,
+    pub evaluation: Evaluation,
+}
+
+/// Tries every combination of options in `ranges` against `ground_truth`,
+/// using the options in `base` for anything not being swept.
+///
+/// Results are sorted from best to worst F1 score, then by precision.
+/// Results that tie keep the order of `ranges`.
+///
+/// # Panics
+///
+/// - If any lookahead distance in `ranges` is 0.
+#[must_use]
+pub fn sweep(
+    scores: &FrameScores,
+    base: DetectionOptions,
+    ranges: &SweepRanges,
+    ground_truth: &[usize],
+    tolerance: usize,
+) -> Vec<TuneResult> {
+    let mut results = ranges
+        .combinations(base)
+        .into_iter()
+        .map(|options| {
+            assert!(options.lookahead_distance >= 1);
+            let scene_changes = scores.scene_changes(options);
+            TuneResult {
+                options,
+                evaluation: evaluate(&scene_changes, ground_truth, tolerance),
+            }
+        })
+        .collect::<Vec<_>>();
+    results.sort_by(|a, b| {
+        b.evaluation
+            .f1
+            .total_cmp(&a.evaluation.f1)
+            .then(b.evaluation.precision.total_cmp(&a.evaluation.precision))
+    });
+    results
+}
@@ -54,16 +54,22 @@ impl VapoursynthDecoder {
             chroma_sampling: get_chroma_sampling(info)?,
             chroma_sample_position: ChromaSamplePosition::Unknown,
             time_base: get_time_base(info)?,
+            converted_from: None,
         })
     }
 
+    /// Reads the next frame, or `None` after the last one.
+    ///
     /// # Errors
     ///
     /// - If sourcing an invalid Vapoursynth script.
     /// - If using a Vapoursynth script that contains an unsupported video format.
     /// - If a frame cannot be read.
     #[allow(clippy::transmute_ptr_to_ptr)]
-    pub fn read_video_frame<T: Pixel>(&mut self, cfg: &VideoDetails) -> anyhow::Result<Frame<T>> {
+    pub fn read_video_frame<T: Pixel>(
+        &mut self,
+        cfg: &VideoDetails,
+    ) -> anyhow::Result<Option<Frame<T>>> {
         const SB_SIZE_LOG2: usize = 6;
         const SB_SIZE: usize = 1 << SB_SIZE_LOG2;
         const SUBPEL_FILTER_SIZE: usize = 8;
@@ -71,7 +77,7 @@ impl VapoursynthDecoder {
         const LUMA_PADDING: usize = SB_SIZE + FRAME_MARGIN;
 
         if self.frames_read >= self.total_frames {
-            bail!("No frames left");
+            return Ok(None);
         }
 
         let (node, _) = self.env.get_output(OUTPUT_INDEX)?;
@@ -109,7 +115,7 @@ impl VapoursynthDecoder {
                 bytes,
             );
         }
-        Ok(f)
+        Ok(Some(f))
     }
 }
 
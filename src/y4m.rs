@@ -20,6 +20,7 @@ pub fn get_video_details<R: Read>(dec: &y4m::Decoder<R>) -> VideoDetails {
         chroma_sampling,
         chroma_sample_position,
         time_base,
+        converted_from: None,
     }
 }
 
@@ -46,7 +47,7 @@ const fn map_y4m_color_space(
 pub fn read_video_frame<R: Read, T: Pixel>(
     dec: &mut y4m::Decoder<R>,
     cfg: &VideoDetails,
-) -> anyhow::Result<Frame<T>> {
+) -> anyhow::Result<Option<Frame<T>>> {
     const SB_SIZE_LOG2: usize = 6;
     const SB_SIZE: usize = 1 << SB_SIZE_LOG2;
     const SUBPEL_FILTER_SIZE: usize = 8;
@@ -54,19 +55,23 @@ pub fn read_video_frame<R: Read, T: Pixel>(
     const LUMA_PADDING: usize = SB_SIZE + FRAME_MARGIN;
 
     let bytes = dec.get_bytes_per_sample();
-    dec.read_frame()
-        .map(|frame| {
-            let mut f: Frame<T> =
-                Frame::new_with_padding(cfg.width, cfg.height, cfg.chroma_sampling, LUMA_PADDING);
+    let frame = match dec.read_frame() {
+        Ok(frame) => frame,
+        Err(y4m::Error::EOF) => return Ok(None),
+        Err(e) => return Err(e.into()),
+    };
+    let mut f: Frame<T> =
+        Frame::new_with_padding(cfg.width, cfg.height, cfg.chroma_sampling, LUMA_PADDING);
 
-            let (chroma_width, _) = cfg
-                .chroma_sampling
-                .get_chroma_dimensions(cfg.width, cfg.height);
+    let (chroma_width, _) = cfg
+        .chroma_sampling
+        .get_chroma_dimensions(cfg.width, cfg.height);
 
-            f.planes[0].copy_from_raw_u8(frame.get_y_plane(), cfg.width * bytes, bytes);
-            f.planes[1].copy_from_raw_u8(frame.get_u_plane(), chroma_width * bytes, bytes);
-            f.planes[2].copy_from_raw_u8(frame.get_v_plane(), chroma_width * bytes, bytes);
-            f
-        })
-        .map_err(|e| e.into())
+    f.planes[0].copy_from_raw_u8(frame.get_y_plane(), cfg.width * bytes, bytes);
+    // Monochrome input has no chroma planes to copy
+    if cfg.chroma_sampling != ChromaSampling::Cs400 {
+        f.planes[1].copy_from_raw_u8(frame.get_u_plane(), chroma_width * bytes, bytes);
+        f.planes[2].copy_from_raw_u8(frame.get_v_plane(), chroma_width * bytes, bytes);
+    }
+    Ok(Some(f))
 }
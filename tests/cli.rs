@@ -0,0 +1,41 @@
+#![cfg(feature = "binary")]
+
+mod common;
+
+use std::{
+    path::{Path, PathBuf},
+    process::{Command, Output},
+};
+
+use av_scenechange::synthetic::Segment;
+
+fn write_clip(name: &str) -> PathBuf {
+    let data = common::clip(vec![Segment::Scene { frames: 10 }; 3])
+        .to_y4m()
+        .unwrap();
+    let path = Path::new(env!("CARGO_TARGET_TMPDIR")).join(name);
+    std::fs::write(&path, data).unwrap();
+    path
+}
+
+fn run(args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_av-scenechange"))
+        .args(args)
+        .output()
+        .unwrap()
+}
+
+#[test]
+fn fast_detector_options_require_fast_speed() {
+    let path = write_clip("cli-tune.y4m");
+    let path = path.to_str().unwrap();
+    for args in [["--tune", "anime"], ["--threshold", "0.5"]] {
+        let output = run(&[&[path][..], &args].concat());
+        assert!(!output.status.success(), "{args:?}");
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        assert!(stderr.contains(args[0]), "{stderr}");
+
+        let output = run(&[&[path, "--speed", "1"][..], &args].concat());
+        assert!(output.status.success(), "{args:?}");
+    }
+}
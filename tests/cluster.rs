@@ -0,0 +1,34 @@
+mod common;
+
+use av_scenechange::{synthetic::Segment, DetectionOptions, DetectionResults, FrameOverrides};
+use common::{clip, detect_results, fast};
+
+fn detect(segments: Vec<Segment>, scene_cluster_threshold: Option<f64>) -> DetectionResults {
+    let opts = DetectionOptions {
+        scene_cluster_threshold,
+        ..fast()
+    };
+    detect_results(&clip(segments), opts, &FrameOverrides::default())
+}
+
+#[test]
+fn recurring_scenes_share_cluster() {
+    // The synthetic clip repeats its 6 scenes
+    let segments = vec![Segment::Scene { frames: 10 }; 9];
+    let results = detect(segments.clone(), Some(10.0));
+    assert_eq!(results.scene_changes.len(), 9);
+    assert_eq!(
+        results.scene_clusters,
+        Some(vec![0, 1, 2, 3, 4, 5, 0, 1, 2])
+    );
+
+    assert_eq!(detect(segments, None).scene_clusters, None);
+}
+
+#[test]
+fn threshold_merges_clusters() {
+    let results = detect(vec![Segment::Scene { frames: 10 }; 6], Some(f64::INFINITY));
+    assert_eq!(results.scene_clusters, Some(vec![0; 6]));
+    let results = detect(vec![Segment::Scene { frames: 10 }; 6], Some(0.0));
+    assert_eq!(results.scene_clusters, Some(vec![0, 1, 2, 3, 4, 5]));
+}
@@ -0,0 +1,85 @@
+//! Helpers shared by the integration tests.
+//!
+//! Each test binary uses a different subset of these.
+#![allow(dead_code)]
+
+use av_scenechange::{
+    decoder::Decoder,
+    detect_scene_changes_with_overrides, score_frames,
+    synthetic::{Segment, SyntheticClip},
+    DetectionOptions, DetectionResults, FrameOverrides, FrameScores, HsvWeights,
+    SceneDetectionSpeed,
+};
+
+pub fn clip(segments: Vec<Segment>) -> SyntheticClip {
+    SyntheticClip {
+        segments,
+        ..SyntheticClip::default()
+    }
+}
+
+pub fn fast() -> DetectionOptions {
+    DetectionOptions {
+        analysis_speed: SceneDetectionSpeed::Fast,
+        ..DetectionOptions::default()
+    }
+}
+
+/// Runs detection, checking that replaying frame scores gives the same
+/// scene changes if only the fast detector is used
+pub fn detect_results(
+    clip: &SyntheticClip,
+    opts: DetectionOptions,
+    overrides: &FrameOverrides,
+) -> DetectionResults {
+    let data = clip.to_y4m().unwrap();
+    let mut reader = data.as_slice();
+    let mut dec = Decoder::Y4m(y4m::Decoder::new(&mut reader).unwrap());
+    let results = if clip.bit_depth == 8 {
+        detect_scene_changes_with_overrides::<_, u8>(&mut dec, opts, overrides, None, None)
+    } else {
+        detect_scene_changes_with_overrides::<_, u16>(&mut dec, opts, overrides, None, None)
+    }
+    .unwrap();
+    assert_eq!(results.frame_count, clip.frame_count());
+
+    let standard_zone = overrides
+        .zones
+        .iter()
+        .any(|zone| zone.analysis_speed == Some(SceneDetectionSpeed::Standard));
+    if opts.analysis_speed == SceneDetectionSpeed::Fast && opts.ensemble.is_none() && !standard_zone
+    {
+        let scores = score(clip, HsvWeights::default());
+        assert_eq!(
+            scores.scene_changes_with_overrides(opts, overrides),
+            results.scene_changes
+        );
+    }
+
+    results
+}
+
+/// Scores every frame of `clip`
+pub fn score(clip: &SyntheticClip, hsv_weights: HsvWeights) -> FrameScores {
+    let data = clip.to_y4m().unwrap();
+    let mut reader = data.as_slice();
+    let mut dec = Decoder::Y4m(y4m::Decoder::new(&mut reader).unwrap());
+    if clip.bit_depth == 8 {
+        score_frames::<_, u8>(&mut dec, hsv_weights, None, None)
+    } else {
+        score_frames::<_, u16>(&mut dec, hsv_weights, None, None)
+    }
+    .unwrap()
+}
+
+pub fn detect(clip: &SyntheticClip, opts: DetectionOptions) -> Vec<usize> {
+    detect_results(clip, opts, &FrameOverrides::default()).scene_changes
+}
+
+pub fn detect_with_overrides(
+    clip: &SyntheticClip,
+    opts: DetectionOptions,
+    overrides: &FrameOverrides,
+) -> Vec<usize> {
+    detect_results(clip, opts, overrides).scene_changes
+}
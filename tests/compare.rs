@@ -0,0 +1,61 @@
+mod common;
+
+use av_scenechange::{
+    compare::{align, compare},
+    synthetic::Segment,
+    DetectionOptions, DetectionResults, FrameOverrides,
+};
+use common::{clip, detect_results, fast};
+
+fn detect(segments: Vec<Segment>, scene_hashes: bool) -> DetectionResults {
+    let opts = DetectionOptions {
+        scene_hashes,
+        ..fast()
+    };
+    detect_results(&clip(segments), opts, &FrameOverrides::default())
+}
+
+#[test]
+fn matches_scenes_cut_differently() {
+    // The same scenes, each cut 2 frames longer
+    let left = detect(vec![Segment::Scene { frames: 20 }; 6], true);
+    let right = detect(vec![Segment::Scene { frames: 22 }; 3], true);
+    assert_eq!(left.scene_hashes.as_ref().unwrap().len(), 6);
+
+    let comparison = compare(&left, &right, 10).unwrap();
+    let pairs = comparison
+        .matches
+        .iter()
+        .map(|scene| (scene.left_scene, scene.right_scene))
+        .collect::<Vec<_>>();
+    assert_eq!(pairs, vec![(0, 0), (1, 1), (2, 2)]);
+    assert_eq!(
+        comparison
+            .matches
+            .iter()
+            .map(|scene| (scene.left_frame, scene.right_frame))
+            .collect::<Vec<_>>(),
+        vec![(0, 0), (20, 22), (40, 44)]
+    );
+    assert!((comparison.left_coverage - 0.5).abs() < 1e-9);
+    assert!((comparison.right_coverage - 1.0).abs() < 1e-9);
+}
+
+#[test]
+fn requires_scene_hashes() {
+    let results = detect(vec![Segment::Scene { frames: 10 }], false);
+    assert_eq!(results.scene_hashes, None);
+    assert!(compare(&results, &results, 10).is_err());
+}
+
+#[test]
+fn alignment_keeps_order() {
+    // The last scene of `left` is repeated at the start of `right`,
+    // but matching it would lose the three scenes after it
+    let left = [0b0000, 0b1111, 0b1100_0000, 0xff00];
+    let right = [0xff00, 0b0000, 0b1111, 0b1100_0000];
+    assert_eq!(align(&left, &right, 0), vec![(0, 1), (1, 2), (2, 3)]);
+    // Closer matches win ties
+    assert_eq!(align(&[0b0011], &[0b0111, 0b0011], 1), vec![(0, 1)]);
+    assert_eq!(align(&left, &[], 64), vec![]);
+}
@@ -0,0 +1,114 @@
+use av_scenechange::edl::{format_timecode, parse, parse_timecode, write};
+
+#[test]
+fn timecodes_round_trip() {
+    for frame in [0, 1, 1799, 1800, 1801, 17982, 17983, 107_892] {
+        let timecode = format_timecode(frame, 30000.0 / 1001.0, true);
+        assert_eq!(parse_timecode(&timecode, 30000.0 / 1001.0).unwrap(), frame);
+        let timecode = format_timecode(frame, 25.0, false);
+        assert_eq!(parse_timecode(&timecode, 25.0).unwrap(), frame);
+    }
+    // The first two frame numbers of each minute are skipped,
+    // except every tenth minute
+    assert_eq!(format_timecode(1800, 29.97, true), "00:01:00;02");
+    assert_eq!(format_timecode(17982, 29.97, true), "00:10:00;00");
+    assert_eq!(format_timecode(90_061, 25.0, false), "01:00:02:11");
+}
+
+#[test]
+fn written_edl_round_trips() {
+    let scene_changes = [0, 48, 120, 1850];
+    let edl = write("clip", &scene_changes, 2000, 24.0, Some("clip.y4m"));
+    assert!(edl.starts_with("TITLE: clip\nFCM: NON-DROP FRAME\n"));
+    assert!(edl.contains(
+        "002  AX       V     C        00:00:02:00 00:00:05:00 00:00:02:00 00:00:05:00\n\
+         * FROM CLIP NAME: clip.y4m\n"
+    ));
+    assert_eq!(parse(&edl, 24.0).unwrap(), scene_changes);
+
+    let edl = write("clip", &scene_changes, 2000, 29.97, None);
+    assert!(edl.contains("FCM: DROP FRAME\n"));
+    assert_eq!(parse(&edl, 29.97).unwrap(), scene_changes);
+}
+
+#[cfg(feature = "serialize")]
+#[test]
+fn written_otio_round_trips() {
+    use av_scenechange::otio;
+
+    let scene_changes = [0, 48, 120, 1850];
+    let timeline = otio::write("clip", &scene_changes, 2000, 24.0, Some("clip.y4m")).unwrap();
+    assert_eq!(otio::parse(&timeline, None).unwrap(), scene_changes);
+    // Times are converted to the requested frame rate
+    assert_eq!(
+        otio::parse(&timeline, Some(48.0)).unwrap(),
+        [0, 96, 240, 3700]
+    );
+    assert!(otio::parse("{}", None).is_err());
+}
+
+#[cfg(feature = "serialize")]
+#[test]
+fn otio_clips_without_source_range_use_available_range() {
+    use av_scenechange::otio;
+    use serde_json::{json, Value};
+
+    let range = |duration: usize| {
+        json!({
+            "OTIO_SCHEMA": "TimeRange.1",
+            "start_time": { "OTIO_SCHEMA": "RationalTime.1", "rate": 24.0, "value": 0.0 },
+            "duration": { "OTIO_SCHEMA": "RationalTime.1", "rate": 24.0, "value": duration },
+        })
+    };
+    let timeline = |clips: Vec<Value>| {
+        json!({
+            "OTIO_SCHEMA": "Timeline.1",
+            "tracks": {
+                "OTIO_SCHEMA": "Stack.1",
+                "children": [{ "OTIO_SCHEMA": "Track.1", "kind": "Video", "children": clips }],
+            },
+        })
+        .to_string()
+    };
+    let clips = vec![
+        json!({
+            "OTIO_SCHEMA": "Clip.2",
+            "name": "Active reference",
+            "source_range": null,
+            "media_references": {
+                "DEFAULT_MEDIA": { "available_range": range(100) },
+                "PROXY": { "available_range": range(48) },
+            },
+            "active_media_reference_key": "PROXY",
+        }),
+        json!({
+            "OTIO_SCHEMA": "Clip.1",
+            "name": "Single reference",
+            "source_range": null,
+            "media_reference": { "available_range": range(72) },
+        }),
+        json!({
+            "OTIO_SCHEMA": "Clip.2",
+            "name": "Source range",
+            "source_range": range(30),
+        }),
+    ];
+    assert_eq!(
+        otio::parse(&timeline(clips.clone()), None).unwrap(),
+        [0, 48, 120]
+    );
+
+    let mut clips = clips;
+    clips.insert(
+        1,
+        json!({
+            "OTIO_SCHEMA": "Clip.2",
+            "name": "Missing reference",
+            "source_range": null,
+            "media_references": { "DEFAULT_MEDIA": { "available_range": null } },
+            "active_media_reference_key": "DEFAULT_MEDIA",
+        }),
+    );
+    let error = otio::parse(&timeline(clips), None).unwrap_err();
+    assert!(error.to_string().contains("Missing reference"), "{error}");
+}
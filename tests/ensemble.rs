@@ -0,0 +1,98 @@
+mod common;
+
+use av_scenechange::{
+    synthetic::{Segment, SyntheticClip},
+    DetectionOptions, DetectionResults, EnsembleMember, EnsembleOptions, FrameOverrides,
+};
+
+/// Two cuts every detector sees around a fade, whose frames only
+/// the histogram detector mistakes for scene changes without flash detection
+fn clip() -> SyntheticClip {
+    common::clip(vec![
+        Segment::Scene { frames: 20 },
+        Segment::Scene { frames: 20 },
+        Segment::FadeOut { frames: 10 },
+        Segment::Scene { frames: 20 },
+    ])
+}
+
+fn detect(ensemble: EnsembleOptions) -> DetectionResults {
+    let opts = DetectionOptions {
+        detect_flashes: false,
+        ensemble: Some(ensemble),
+        ..DetectionOptions::default()
+    };
+    common::detect_results(&clip(), opts, &FrameOverrides::default())
+}
+
+const ALL: [EnsembleMember; 3] = [
+    EnsembleMember::Standard,
+    EnsembleMember::PixelDifference,
+    EnsembleMember::Histogram,
+];
+
+#[test]
+fn min_agreement() {
+    // The fade has a third of the votes
+    let results = detect(EnsembleOptions {
+        min_agreement: 0.4,
+        ..EnsembleOptions::default()
+    });
+    assert_eq!(results.scene_changes, vec![0, 20, 50]);
+    assert_eq!(
+        results.ensemble_votes.unwrap(),
+        vec![Vec::new(), ALL.to_vec(), ALL.to_vec()]
+    );
+
+    let results = detect(EnsembleOptions {
+        min_agreement: 0.3,
+        ..EnsembleOptions::default()
+    });
+    let fade = 40..50;
+    assert_eq!(
+        results.scene_changes,
+        [0, 20]
+            .into_iter()
+            .chain(fade.clone())
+            .chain([50])
+            .collect::<Vec<_>>()
+    );
+    let votes = results.ensemble_votes.unwrap();
+    assert_eq!(votes.len(), results.scene_changes.len());
+    for (frameno, votes) in results.scene_changes.iter().zip(votes) {
+        let expected = match frameno {
+            0 => Vec::new(),
+            frameno if fade.contains(frameno) => vec![EnsembleMember::Histogram],
+            _ => ALL.to_vec(),
+        };
+        assert_eq!(votes, expected, "{frameno}");
+    }
+}
+
+#[test]
+fn weights() {
+    // A majority of the weight is enough on its own
+    let results = detect(EnsembleOptions {
+        histogram: 3.0,
+        ..EnsembleOptions::default()
+    });
+    assert_eq!(results.scene_changes.len(), 13);
+    let results = detect(EnsembleOptions {
+        standard: 2.0,
+        min_agreement: 0.3,
+        ..EnsembleOptions::default()
+    });
+    assert_eq!(results.scene_changes, vec![0, 20, 50]);
+
+    // Detectors with no weight are not run, so they never vote
+    let results = detect(EnsembleOptions {
+        histogram: 0.0,
+        min_agreement: 0.0,
+        ..EnsembleOptions::default()
+    });
+    assert_eq!(results.scene_changes, vec![0, 20, 50]);
+    assert_eq!(
+        results.ensemble_votes.unwrap()[1],
+        vec![EnsembleMember::Standard, EnsembleMember::PixelDifference]
+    );
+}
@@ -0,0 +1,129 @@
+use std::path::{Path, PathBuf};
+
+use av_scenechange::eval::{evaluate, read_cut_list, read_frame_ranges, MatchedCut};
+
+fn write(name: &str, contents: &str) -> PathBuf {
+    let path = Path::new(env!("CARGO_TARGET_TMPDIR")).join(name);
+    std::fs::write(&path, contents).unwrap();
+    path
+}
+
+#[test]
+fn matches_within_tolerance() {
+    let evaluation = evaluate(&[0, 12, 31, 70], &[0, 10, 30, 50], 2);
+    assert_eq!(
+        evaluation.matched,
+        vec![
+            MatchedCut {
+                expected: 10,
+                detected: 12,
+                offset: 2,
+            },
+            MatchedCut {
+                expected: 30,
+                detected: 31,
+                offset: 1,
+            },
+        ]
+    );
+    assert_eq!(evaluation.missed, vec![50]);
+    assert_eq!(evaluation.spurious, vec![70]);
+    assert!((evaluation.precision - 2.0 / 3.0).abs() < f64::EPSILON);
+    assert!((evaluation.recall - 2.0 / 3.0).abs() < f64::EPSILON);
+    assert!((evaluation.f1 - 2.0 / 3.0).abs() < f64::EPSILON);
+
+    // Frame 0 is ignored, and nothing is further than the tolerance
+    let evaluation = evaluate(&[0, 13], &[10], 2);
+    assert!(evaluation.matched.is_empty());
+    assert_eq!(evaluation.missed, vec![10]);
+    assert_eq!(evaluation.spurious, vec![13]);
+    assert!(evaluation.f1.abs() < f64::EPSILON);
+}
+
+#[test]
+fn matches_each_cut_once() {
+    // 11 is within the tolerance of both 10 and 12, but only matches
+    // the closer one, leaving the other for 14
+    let evaluation = evaluate(&[11, 14], &[10, 12], 2);
+    assert_eq!(
+        evaluation.matched,
+        vec![
+            MatchedCut {
+                expected: 10,
+                detected: 11,
+                offset: 1,
+            },
+            MatchedCut {
+                expected: 12,
+                detected: 14,
+                offset: 2,
+            },
+        ]
+    );
+    // Without a second detection, the other reference cut is missed
+    let evaluation = evaluate(&[11], &[10, 12], 2);
+    assert_eq!(evaluation.matched.len(), 1);
+    assert_eq!(evaluation.missed.len(), 1);
+    assert!(evaluation.spurious.is_empty());
+}
+
+#[test]
+fn empty_lists() {
+    let evaluation = evaluate(&[0], &[0], 2);
+    assert!((evaluation.precision - 1.0).abs() < f64::EPSILON);
+    assert!((evaluation.recall - 1.0).abs() < f64::EPSILON);
+    assert!((evaluation.f1 - 1.0).abs() < f64::EPSILON);
+
+    let evaluation = evaluate(&[], &[10], 2);
+    assert!((evaluation.precision - 1.0).abs() < f64::EPSILON);
+    assert!(evaluation.recall.abs() < f64::EPSILON);
+    assert!(evaluation.f1.abs() < f64::EPSILON);
+
+    let evaluation = evaluate(&[10], &[], 2);
+    assert!(evaluation.precision.abs() < f64::EPSILON);
+    assert!((evaluation.recall - 1.0).abs() < f64::EPSILON);
+    assert!(!evaluation.f1.is_nan());
+}
+
+#[test]
+fn reads_csv_cut_lists() {
+    let path = write("eval-plain.csv", "0\n24\n\n96, ignored\n");
+    assert_eq!(read_cut_list(path, None).unwrap(), vec![0, 24, 96]);
+
+    let path = write(
+        "eval-header.csv",
+        "scene, start frame, end frame\n1, 0, 23\n2, 24, 95\n",
+    );
+    assert_eq!(read_cut_list(path, None).unwrap(), vec![0, 24]);
+
+    let path = write("eval-malformed.csv", "0\n24\nnot a frame\n");
+    let error = read_cut_list(path, None).unwrap_err();
+    assert!(error.to_string().contains("line 3"), "{error}");
+
+    // Only the first line is a header, even if the rows don't start with a frame
+    let path = write("eval-labels.csv", "shot,frame\nA,24\nB,48\n");
+    assert_eq!(read_cut_list(path, None).unwrap(), vec![24, 48]);
+
+    let path = write("eval-two-headers.csv", "shot,frame\nshot,frame\nA,24\n");
+    let error = read_cut_list(path, None).unwrap_err();
+    assert!(error.to_string().contains("line 2"), "{error}");
+
+    let path = Path::new(env!("CARGO_TARGET_TMPDIR")).join("eval-missing.csv");
+    assert!(read_cut_list(path, None).is_err());
+}
+
+#[test]
+fn reads_csv_frame_ranges() {
+    let path = write("ranges-plain.csv", "10, 20\n\n30,40\n");
+    assert_eq!(read_frame_ranges(path).unwrap(), vec![10..20, 30..40]);
+
+    let path = write("ranges-labels.csv", "shot,start,end\nA,10,20\nB,30,40\n");
+    assert_eq!(read_frame_ranges(path).unwrap(), vec![10..20, 30..40]);
+
+    let path = write(
+        "ranges-two-headers.csv",
+        "shot,start,end\nshot,start,end\nA,10,20\n",
+    );
+    let error = read_frame_ranges(path).unwrap_err();
+    assert!(error.to_string().contains("line 2"), "{error}");
+}
@@ -0,0 +1,674 @@
+#![cfg(feature = "ffmpeg")]
+
+extern crate ffmpeg_the_third as ffmpeg;
+
+use std::path::{Path, PathBuf};
+
+use av_scenechange::{
+    decoder::Decoder,
+    detect_scene_changes,
+    ffmpeg::{FfmpegDecoder, FfmpegOptions},
+    DetectionOptions, SceneDetectionSpeed,
+};
+use ffmpeg::{codec, encoder, format, frame, media, Dictionary, Packet, Rational};
+use rav1e::prelude::{ChromaSampling, Frame, Pixel};
+
+/// A video to encode with the ffmpeg libraries.
+struct TestVideo {
+    codec: codec::Id,
+    format: format::Pixel,
+    width: u32,
+    height: u32,
+    max_b_frames: usize,
+    /// The presentation time of each frame, in milliseconds
+    timestamps: Vec<i64>,
+    /// The frame number of the first frame of each scene after the first
+    scene_changes: Vec<usize>,
+    /// Whether the stream is an attached picture, such as cover art
+    attached_picture: bool,
+}
+
+impl Default for TestVideo {
+    fn default() -> Self {
+        TestVideo {
+            codec: codec::Id::FFV1,
+            format: format::Pixel::YUV420P,
+            width: 160,
+            height: 90,
+            max_b_frames: 0,
+            timestamps: (0..60).map(|frameno| frameno * 40).collect(),
+            scene_changes: vec![20, 40],
+            attached_picture: false,
+        }
+    }
+}
+
+impl TestVideo {
+    /// The luma of the frames in each scene
+    const LUMA: [u8; 3] = [50, 180, 110];
+
+    fn scene(&self, frameno: usize) -> usize {
+        self.scene_changes
+            .iter()
+            .filter(|&&start| start <= frameno)
+            .count()
+    }
+
+    /// Encodes the video to a file named `name` in the target directory.
+    fn write(&self, name: &str) -> PathBuf {
+        write_streams(name, &[(self, &[])])
+    }
+
+    /// The luma of a pixel: a gradient moving by a pixel per frame,
+    /// brightened by the luma of the frame's scene
+    fn sample(&self, frameno: usize, x: usize, y: usize) -> u8 {
+        let luma = Self::LUMA[self.scene(frameno) % Self::LUMA.len()];
+        luma.saturating_add(((x + y + frameno) % 32) as u8)
+    }
+
+    /// Draws `frameno` into `frame`, with neutral chroma.
+    fn fill(&self, frame: &mut frame::Video, frameno: usize) {
+        let sample = |x: usize, y: usize| self.sample(frameno, x, y);
+        let (width, height) = (self.width as usize, self.height as usize);
+        match self.format {
+            format::Pixel::YUV420P => {
+                let stride = frame.stride(0);
+                let data = frame.data_mut(0);
+                for y in 0..height {
+                    for x in 0..width {
+                        data[y * stride + x] = sample(x, y);
+                    }
+                }
+                for plane in 1..3 {
+                    let stride = frame.stride(plane);
+                    let data = frame.data_mut(plane);
+                    for y in 0..height.div_ceil(2) {
+                        data[y * stride..y * stride + width.div_ceil(2)].fill(128);
+                    }
+                }
+            }
+            format::Pixel::NV12 => {
+                let stride = frame.stride(0);
+                let data = frame.data_mut(0);
+                for y in 0..height {
+                    for x in 0..width {
+                        data[y * stride + x] = sample(x, y);
+                    }
+                }
+                // Interleaved U and V
+                let stride = frame.stride(1);
+                let data = frame.data_mut(1);
+                for y in 0..height.div_ceil(2) {
+                    data[y * stride..y * stride + width.div_ceil(2) * 2].fill(128);
+                }
+            }
+            format::Pixel::YUV420P10LE | format::Pixel::P010LE | format::Pixel::YUV420P10BE => {
+                // 10-bit samples, which P010 stores in the high bits
+                let shift = if self.format == format::Pixel::P010LE {
+                    6
+                } else {
+                    0
+                };
+                let to_bytes: fn(u16) -> [u8; 2] = if self.format == format::Pixel::YUV420P10BE {
+                    u16::to_be_bytes
+                } else {
+                    u16::to_le_bytes
+                };
+                let stride = frame.stride(0);
+                let data = frame.data_mut(0);
+                for y in 0..height {
+                    for x in 0..width {
+                        let pixel = to_bytes((u16::from(sample(x, y)) << 2) << shift);
+                        data[y * stride + x * 2..][..2].copy_from_slice(&pixel);
+                    }
+                }
+                let neutral = to_bytes(512 << shift);
+                // P010 has a single plane of interleaved U and V
+                let (planes, samples) = if self.format == format::Pixel::P010LE {
+                    (1..2, width.div_ceil(2) * 2)
+                } else {
+                    (1..3, width.div_ceil(2))
+                };
+                for plane in planes {
+                    let stride = frame.stride(plane);
+                    let data = frame.data_mut(plane);
+                    for y in 0..height.div_ceil(2) {
+                        for x in 0..samples {
+                            data[y * stride + x * 2..][..2].copy_from_slice(&neutral);
+                        }
+                    }
+                }
+            }
+            format::Pixel::RGB24 => {
+                // Grey, so every channel is the luma
+                let stride = frame.stride(0);
+                let data = frame.data_mut(0);
+                for y in 0..height {
+                    for x in 0..width {
+                        data[y * stride + x * 3..][..3].fill(sample(x, y));
+                    }
+                }
+            }
+            format => panic!("Unsupported test format {format:?}"),
+        }
+    }
+}
+
+/// Encodes each video as a stream of a file named `name` in the target directory,
+/// tagged with its metadata.
+fn write_streams(name: &str, streams: &[(&TestVideo, &[(&str, &str)])]) -> PathBuf {
+    ffmpeg::init().unwrap();
+    let path = Path::new(env!("CARGO_TARGET_TMPDIR")).join(name);
+
+    let mut output = format::output(&path).unwrap();
+    let global_header = output
+        .format()
+        .flags()
+        .contains(format::Flags::GLOBAL_HEADER);
+    let time_base = Rational::new(1, 1000);
+    let mut encoders = Vec::new();
+    for &(video, tags) in streams {
+        let codec = encoder::find(video.codec).unwrap();
+        let mut stream = output.add_stream(codec).unwrap();
+        let mut encoder = codec::context::Context::new_with_codec(codec)
+            .encoder()
+            .video()
+            .unwrap();
+        encoder.set_width(video.width);
+        encoder.set_height(video.height);
+        encoder.set_format(video.format);
+        encoder.set_time_base(time_base);
+        encoder.set_max_b_frames(video.max_b_frames);
+        if global_header {
+            encoder.set_flags(codec::Flags::GLOBAL_HEADER);
+        }
+        let encoder = encoder.open_as(codec).unwrap();
+        stream.set_parameters(&encoder);
+        stream.set_time_base(time_base);
+        if video.attached_picture {
+            // SAFETY: The stream belongs to `output`, which is still open
+            unsafe {
+                (*stream.as_mut_ptr()).disposition =
+                    format::stream::Disposition::ATTACHED_PIC.bits();
+            }
+        }
+        let mut metadata = Dictionary::new();
+        for &(key, value) in tags {
+            metadata.set(key, value);
+        }
+        stream.set_metadata(metadata);
+        encoders.push(encoder);
+    }
+    output.write_header().unwrap();
+    let stream_time_bases = (0..streams.len())
+        .map(|index| output.stream(index).unwrap().time_base())
+        .collect::<Vec<_>>();
+
+    let write_packets = |encoder: &mut encoder::video::Encoder,
+                         index: usize,
+                         output: &mut format::context::Output| {
+        let mut packet = Packet::empty();
+        while encoder.receive_packet(&mut packet).is_ok() {
+            packet.set_stream(index);
+            packet.rescale_ts(time_base, stream_time_bases[index]);
+            packet.write_interleaved(output).unwrap();
+        }
+    };
+    let frame_count = streams
+        .iter()
+        .map(|(video, _)| video.timestamps.len())
+        .max()
+        .unwrap_or_default();
+    for frameno in 0..frame_count {
+        for (index, (&(video, _), encoder)) in streams.iter().zip(&mut encoders).enumerate() {
+            let Some(&timestamp) = video.timestamps.get(frameno) else {
+                continue;
+            };
+            let mut frame = frame::Video::new(video.format, video.width, video.height);
+            video.fill(&mut frame, frameno);
+            frame.set_pts(Some(timestamp));
+            encoder.send_frame(&frame).unwrap();
+            write_packets(encoder, index, &mut output);
+        }
+    }
+    for (index, encoder) in encoders.iter_mut().enumerate() {
+        encoder.send_eof().unwrap();
+        write_packets(encoder, index, &mut output);
+    }
+    output.write_trailer().unwrap();
+    path
+}
+
+/// Decodes every frame of the video at `path`, returning the decoder
+/// and the mean luma of each frame.
+fn decode<T: Pixel + Into<u64>>(path: &Path) -> (FfmpegDecoder, Vec<f64>) {
+    let mut dec = FfmpegDecoder::new(path, &FfmpegOptions::default()).unwrap();
+    let (width, height) = (dec.video_details.width, dec.video_details.height);
+    let mut means = Vec::new();
+    while let Some(frame) = dec.read_video_frame::<T>().unwrap() {
+        let plane = &frame.planes[0];
+        let sum = (0..height)
+            .flat_map(|y| plane.row(y as isize)[..width].iter().map(|&p| p.into()))
+            .sum::<u64>();
+        means.push(sum as f64 / (width * height) as f64);
+    }
+    (dec, means)
+}
+
+fn detect(path: &Path) -> av_scenechange::DetectionResults {
+    detect_stream(path, &FfmpegOptions::default()).unwrap()
+}
+
+fn detect_stream(
+    path: &Path,
+    ffmpeg_opts: &FfmpegOptions,
+) -> anyhow::Result<av_scenechange::DetectionResults> {
+    let dec = FfmpegDecoder::new(path, ffmpeg_opts)?;
+    let bit_depth = dec.video_details.bit_depth;
+    let mut dec = Decoder::<std::io::Empty>::Ffmpeg(dec);
+    let opts = DetectionOptions {
+        analysis_speed: SceneDetectionSpeed::Fast,
+        ..DetectionOptions::default()
+    };
+    if bit_depth == 8 {
+        detect_scene_changes::<_, u8>(&mut dec, opts, None, None)
+    } else {
+        detect_scene_changes::<_, u16>(&mut dec, opts, None, None)
+    }
+}
+
+#[test]
+fn reports_variable_frame_rate_timestamps() {
+    // 25 fps, then 10 fps after the first scene change, then a pause of a second
+    let mut timestamps = (0..20).map(|frameno| frameno * 40).collect::<Vec<_>>();
+    timestamps.extend((0..20).map(|frameno| 800 + frameno * 100));
+    timestamps.extend((0..20).map(|frameno| 3800 + frameno * 40));
+    let video = TestVideo {
+        timestamps: timestamps.clone(),
+        ..TestVideo::default()
+    };
+    let path = video.write("vfr.mkv");
+
+    let (dec, means) = decode::<u8>(&path);
+    assert_eq!(means.len(), timestamps.len());
+    let expected = timestamps
+        .iter()
+        .map(|&timestamp| timestamp as f64 / 1000.0)
+        .collect::<Vec<_>>();
+    for (&actual, &expected) in dec.timestamps().iter().zip(&expected) {
+        assert!((actual - expected).abs() < 1e-6, "{actual} != {expected}");
+    }
+
+    let results = detect(&path);
+    assert_eq!(results.scene_changes, vec![0, 20, 40]);
+    let times = results.scene_change_times.unwrap();
+    assert_eq!(times.len(), 3);
+    for (actual, expected) in times.into_iter().zip([0.0, 0.8, 3.8]) {
+        let actual = actual.unwrap();
+        assert!((actual - expected).abs() < 1e-6, "{actual} != {expected}");
+    }
+}
+
+#[test]
+fn decodes_reordered_frames_in_order() {
+    let video = TestVideo {
+        codec: codec::Id::MPEG4,
+        max_b_frames: 3,
+        ..TestVideo::default()
+    };
+    let path = video.write("b_frames.mkv");
+
+    // The last frames are only returned once the decoder is flushed
+    let (dec, means) = decode::<u8>(&path);
+    assert_eq!(means.len(), video.timestamps.len());
+    for (frameno, mean) in means.into_iter().enumerate() {
+        // The gradient adds 15.5 on average
+        let expected = f64::from(TestVideo::LUMA[video.scene(frameno)]) + 15.5;
+        assert!((mean - expected).abs() < 4.0, "frame {frameno}: {mean}");
+    }
+    // Frames are returned in presentation order
+    for (&actual, &expected) in dec.timestamps().iter().zip(&video.timestamps) {
+        assert!((actual - expected as f64 / 1000.0).abs() < 1e-6);
+    }
+
+    let results = detect(&path);
+    assert_eq!(results.frame_count, video.timestamps.len());
+    assert_eq!(results.scene_changes, vec![0, 20, 40]);
+}
+
+#[test]
+fn reading_past_the_end_returns_none() {
+    let video = TestVideo {
+        codec: codec::Id::MPEG4,
+        max_b_frames: 3,
+        timestamps: (0..10).map(|frameno| frameno * 40).collect(),
+        ..TestVideo::default()
+    };
+    let path = video.write("short.mkv");
+    let mut dec = FfmpegDecoder::new(&path, &FfmpegOptions::default()).unwrap();
+    for _ in 0..10 {
+        assert!(dec.read_video_frame::<u8>().unwrap().is_some());
+    }
+    assert!(dec.read_video_frame::<u8>().unwrap().is_none());
+    assert!(dec.read_video_frame::<u8>().unwrap().is_none());
+}
+
+#[test]
+fn converts_semi_planar_frames() {
+    let video = TestVideo {
+        codec: codec::Id::RAWVIDEO,
+        format: format::Pixel::NV12,
+        ..TestVideo::default()
+    };
+    let path = video.write("nv12.nut");
+
+    let (dec, means) = decode::<u8>(&path);
+    assert_eq!(dec.video_details.converted_from, Some("nv12"));
+    assert_eq!(dec.video_details.bit_depth, 8);
+    assert_eq!(dec.video_details.chroma_sampling, ChromaSampling::Cs420);
+    assert_eq!(means.len(), video.timestamps.len());
+    for (frameno, mean) in means.into_iter().enumerate() {
+        // Only the chroma planes are rearranged, so the luma is unchanged
+        let expected = f64::from(TestVideo::LUMA[video.scene(frameno)]) + 15.5;
+        assert!((mean - expected).abs() < 0.5, "frame {frameno}: {mean}");
+    }
+    assert_eq!(detect(&path).scene_changes, vec![0, 20, 40]);
+
+    // Supported formats are read as they are
+    let path = TestVideo::default().write("yuv420p.mkv");
+    let (dec, _) = decode::<u8>(&path);
+    assert_eq!(dec.video_details.converted_from, None);
+}
+
+#[test]
+fn converts_high_bit_depth_and_rgb_frames() {
+    for (format, name, bit_depth, chroma_sampling) in [
+        (format::Pixel::P010LE, "p010le", 10, ChromaSampling::Cs420),
+        (
+            format::Pixel::YUV420P10BE,
+            "yuv420p10be",
+            10,
+            ChromaSampling::Cs420,
+        ),
+        (format::Pixel::RGB24, "rgb24", 8, ChromaSampling::Cs444),
+    ] {
+        let video = TestVideo {
+            codec: codec::Id::RAWVIDEO,
+            format,
+            ..TestVideo::default()
+        };
+        let path = video.write(&format!("{name}.nut"));
+
+        let (dec, means) = if bit_depth == 8 {
+            decode::<u8>(&path)
+        } else {
+            decode::<u16>(&path)
+        };
+        assert_eq!(dec.video_details.converted_from, Some(name));
+        assert_eq!(dec.video_details.bit_depth, bit_depth, "{name}");
+        assert_eq!(dec.video_details.chroma_sampling, chroma_sampling, "{name}");
+        assert_eq!(means.len(), video.timestamps.len(), "{name}");
+        for (frameno, mean) in means.into_iter().enumerate() {
+            let luma = f64::from(TestVideo::LUMA[video.scene(frameno)]) + 15.5;
+            let expected = if format == format::Pixel::RGB24 {
+                // Converted to limited range YUV
+                16.0 + luma * 219.0 / 255.0
+            } else {
+                luma * 4.0
+            };
+            assert!(
+                (mean - expected).abs() < expected / 50.0,
+                "{name} frame {frameno}: {mean}"
+            );
+        }
+        assert_eq!(detect(&path).scene_changes, vec![0, 20, 40], "{name}");
+    }
+}
+
+#[test]
+fn copies_padded_rows() {
+    // Widths that aren't a multiple of the alignment ffmpeg pads rows to,
+    // including odd luma and chroma widths
+    for (width, height) in [(130, 74), (98, 54), (97, 41)] {
+        let video = TestVideo {
+            width,
+            height,
+            timestamps: (0..30).map(|frameno| frameno * 40).collect(),
+            scene_changes: vec![10, 20],
+            ..TestVideo::default()
+        };
+        let path = video.write(&format!("{width}x{height}.mkv"));
+
+        let mut dec = FfmpegDecoder::new(&path, &FfmpegOptions::default()).unwrap();
+        assert_eq!(dec.video_details.width, width as usize);
+        assert_eq!(dec.video_details.height, height as usize);
+        for frameno in 0..video.timestamps.len() {
+            let frame = dec.read_video_frame::<u8>().unwrap().unwrap();
+            // FFV1 is lossless, so every pixel must match
+            for y in 0..height as usize {
+                let row = &frame.planes[0].row(y as isize)[..width as usize];
+                for (x, &pixel) in row.iter().enumerate() {
+                    assert_eq!(
+                        pixel,
+                        video.sample(frameno, x, y),
+                        "{width}x{height} {x},{y}"
+                    );
+                }
+            }
+            let (chroma_width, chroma_height) = (width.div_ceil(2), height.div_ceil(2));
+            for plane in &frame.planes[1..] {
+                for y in 0..chroma_height as usize {
+                    let row = &plane.row(y as isize)[..chroma_width as usize];
+                    assert!(row.iter().all(|&pixel| pixel == 128));
+                }
+            }
+        }
+        assert_eq!(detect(&path).scene_changes, vec![0, 10, 20]);
+    }
+}
+
+/// Decodes every frame of the video at `path` with the ffmpeg libraries directly.
+fn decode_raw(path: &Path) -> Vec<frame::Video> {
+    let mut input = format::input(&path).unwrap();
+    let stream = input.streams().best(media::Type::Video).unwrap();
+    let index = stream.index();
+    let mut decoder = codec::context::Context::from_parameters(stream.parameters())
+        .unwrap()
+        .decoder()
+        .video()
+        .unwrap();
+
+    let mut frames = Vec::new();
+    let mut receive = |decoder: &mut codec::decoder::Video| {
+        let mut decoded = frame::Video::empty();
+        while decoder.receive_frame(&mut decoded).is_ok() {
+            frames.push(decoded);
+            decoded = frame::Video::empty();
+        }
+    };
+    for result in input.packets() {
+        let (stream, packet) = result.unwrap();
+        if stream.index() == index {
+            decoder.send_packet(&packet).unwrap();
+            receive(&mut decoder);
+        }
+    }
+    decoder.send_eof().unwrap();
+    receive(&mut decoder);
+    frames
+}
+
+/// Checks that every visible sample of `frame` matches `decoded`,
+/// whose rows are `bytes` bytes per sample and padded to their stride.
+fn assert_planes_match<T: Pixel + Into<u64>>(
+    decoded: &frame::Video,
+    frame: &Frame<T>,
+    bytes: usize,
+) {
+    let (width, height) = (decoded.width() as usize, decoded.height() as usize);
+    for (i, plane) in frame.planes.iter().enumerate() {
+        let (plane_width, plane_height) = if i == 0 {
+            (width, height)
+        } else {
+            (width.div_ceil(2), height.div_ceil(2))
+        };
+        let stride = decoded.stride(i);
+        // Otherwise reading rows back to back would give the same result
+        assert!(stride > plane_width * bytes, "plane {i}: stride {stride}");
+        let data = decoded.data(i);
+        for y in 0..plane_height {
+            let row = &plane.row(y as isize)[..plane_width];
+            for (x, &pixel) in row.iter().enumerate() {
+                let offset = y * stride + x * bytes;
+                let expected = if bytes == 2 {
+                    u64::from(u16::from_le_bytes([data[offset], data[offset + 1]]))
+                } else {
+                    u64::from(data[offset])
+                };
+                assert_eq!(pixel.into(), expected, "plane {i} {x},{y}");
+            }
+        }
+    }
+}
+
+#[test]
+fn copies_planes_with_their_stride() {
+    for (format, bit_depth) in [
+        (format::Pixel::YUV420P, 8),
+        (format::Pixel::YUV420P10LE, 10),
+    ] {
+        let bytes = if bit_depth > 8 { 2 } else { 1 };
+        let video = TestVideo {
+            format,
+            width: 97,
+            height: 41,
+            timestamps: (0..10).map(|frameno| frameno * 40).collect(),
+            ..TestVideo::default()
+        };
+        let path = video.write(&format!("stride-{bit_depth}.mkv"));
+
+        let raw = decode_raw(&path);
+        assert_eq!(raw.len(), video.timestamps.len());
+        let mut dec = FfmpegDecoder::new(&path, &FfmpegOptions::default()).unwrap();
+        assert_eq!(dec.video_details.bit_depth, bit_depth);
+        for decoded in &raw {
+            assert_eq!(decoded.format(), format);
+            if bytes == 2 {
+                let frame = dec.read_video_frame::<u16>().unwrap().unwrap();
+                assert_planes_match(decoded, &frame, bytes);
+            } else {
+                let frame = dec.read_video_frame::<u8>().unwrap().unwrap();
+                assert_planes_match(decoded, &frame, bytes);
+            }
+        }
+    }
+}
+
+#[test]
+fn selects_streams() {
+    let main = TestVideo::default();
+    let second = TestVideo {
+        scene_changes: vec![30],
+        ..TestVideo::default()
+    };
+    let path = write_streams(
+        "angles.mkv",
+        &[
+            (&main, &[("language", "eng"), ("title", "Main angle")]),
+            (&second, &[("language", "jpn"), ("title", "Second Angle")]),
+        ],
+    );
+    let scene_changes = |opts: FfmpegOptions| detect_stream(&path, &opts).unwrap().scene_changes;
+
+    assert_eq!(
+        scene_changes(FfmpegOptions {
+            stream_index: Some(1),
+            ..FfmpegOptions::default()
+        }),
+        vec![0, 30]
+    );
+    assert_eq!(
+        scene_changes(FfmpegOptions {
+            language: Some("eng".to_string()),
+            ..FfmpegOptions::default()
+        }),
+        vec![0, 20, 40]
+    );
+    assert_eq!(
+        scene_changes(FfmpegOptions {
+            language: Some("JPN".to_string()),
+            ..FfmpegOptions::default()
+        }),
+        vec![0, 30]
+    );
+    assert_eq!(
+        scene_changes(FfmpegOptions {
+            title: Some("second".to_string()),
+            ..FfmpegOptions::default()
+        }),
+        vec![0, 30]
+    );
+    // The index takes precedence over the tags
+    assert_eq!(
+        scene_changes(FfmpegOptions {
+            stream_index: Some(0),
+            title: Some("second".to_string()),
+            ..FfmpegOptions::default()
+        }),
+        vec![0, 20, 40]
+    );
+
+    for opts in [
+        FfmpegOptions {
+            stream_index: Some(2),
+            ..FfmpegOptions::default()
+        },
+        FfmpegOptions {
+            language: Some("eng".to_string()),
+            title: Some("second".to_string()),
+            ..FfmpegOptions::default()
+        },
+    ] {
+        assert!(FfmpegDecoder::new(&path, &opts).is_err(), "{opts:?}");
+    }
+}
+
+#[test]
+fn skips_attached_pictures() {
+    let cover = TestVideo {
+        codec: codec::Id::PNG,
+        format: format::Pixel::RGB24,
+        timestamps: vec![0],
+        scene_changes: Vec::new(),
+        attached_picture: true,
+        ..TestVideo::default()
+    };
+    let main = TestVideo::default();
+    let path = write_streams(
+        "cover.mkv",
+        &[
+            (
+                &cover,
+                &[
+                    ("filename", "cover.png"),
+                    ("mimetype", "image/png"),
+                    ("title", "Cover"),
+                ],
+            ),
+            (&main, &[]),
+        ],
+    );
+    let cover_only = |skip_attached_pictures| FfmpegOptions {
+        title: Some("cover".to_string()),
+        skip_attached_pictures,
+        ..FfmpegOptions::default()
+    };
+
+    let results = detect(&path);
+    assert_eq!(results.frame_count, main.timestamps.len());
+    assert_eq!(results.scene_changes, vec![0, 20, 40]);
+    // The cover can only be selected when attached pictures are included
+    assert!(FfmpegDecoder::new(&path, &cover_only(true)).is_err());
+    let results = detect_stream(&path, &cover_only(false)).unwrap();
+    assert_eq!(results.frame_count, 1);
+}
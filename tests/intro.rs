@@ -0,0 +1,88 @@
+mod common;
+
+use av_scenechange::{
+    intro::{find_intro_and_credits, Episode, IntroOptions},
+    synthetic::Segment,
+    DetectionOptions, FrameOverrides,
+};
+use common::{clip, detect_results, fast};
+
+/// An episode with a cold open and body of different lengths,
+/// but the same intro and credits of two scenes each
+fn episode(cold_open: usize, body: usize) -> Episode {
+    let clip = clip(
+        [cold_open, 30, 30, body, 30, 30]
+            .into_iter()
+            .map(|frames| Segment::Scene { frames })
+            .collect(),
+    );
+    let opts = DetectionOptions {
+        scene_hashes: true,
+        ..fast()
+    };
+    Episode {
+        results: detect_results(&clip, opts, &FrameOverrides::default()),
+        fps: 30.0,
+    }
+}
+
+fn ranges(episodes: &[Episode], opts: IntroOptions) -> Vec<[Option<(usize, usize)>; 2]> {
+    find_intro_and_credits(episodes, opts)
+        .unwrap()
+        .into_iter()
+        .map(|markers| {
+            [markers.intro, markers.credits]
+                .map(|range| range.map(|range| (range.start_frame, range.end_frame)))
+        })
+        .collect()
+}
+
+#[test]
+fn finds_intro_and_credits() {
+    let episodes = [episode(20, 40), episode(50, 80), episode(35, 60)];
+    let opts = IntroOptions {
+        search_duration: 4.0,
+        ..IntroOptions::default()
+    };
+    assert_eq!(
+        ranges(&episodes, opts),
+        vec![
+            [Some((20, 80)), Some((120, 180))],
+            [Some((50, 110)), Some((190, 250))],
+            [Some((35, 95)), Some((155, 215))],
+        ]
+    );
+
+    let markers = find_intro_and_credits(&episodes, opts).unwrap();
+    let intro = markers[1].intro.unwrap();
+    assert!((intro.start_time - 50.0 / 30.0).abs() < 1e-9);
+    assert!((intro.end_time - 110.0 / 30.0).abs() < 1e-9);
+}
+
+#[test]
+fn requires_enough_shared_scenes() {
+    let episodes = [episode(20, 40), episode(50, 80)];
+    let opts = IntroOptions {
+        search_duration: 4.0,
+        min_scenes: 3,
+        ..IntroOptions::default()
+    };
+    assert_eq!(ranges(&episodes, opts), vec![[None, None]; 2]);
+    // The intro of the second episode ends too late to be found
+    let opts = IntroOptions {
+        search_duration: 3.0,
+        ..IntroOptions::default()
+    };
+    assert_eq!(ranges(&episodes, opts)[1][0], None);
+}
+
+#[test]
+fn requires_several_episodes_with_hashes() {
+    let episode = episode(20, 40);
+    assert!(
+        find_intro_and_credits(std::slice::from_ref(&episode), IntroOptions::default()).is_err()
+    );
+    let mut without_hashes = episode.clone();
+    without_hashes.results.scene_hashes = None;
+    assert!(find_intro_and_credits(&[episode, without_hashes], IntroOptions::default()).is_err());
+}
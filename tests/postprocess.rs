@@ -0,0 +1,211 @@
+mod common;
+
+use av_scenechange::{synthetic::Segment, DetectionOptions, FrameOverrides};
+use common::{clip, detect, detect_with_overrides, fast};
+
+#[test]
+fn merges_into_most_similar_neighbour() {
+    // The luma of the scenes is 50, 180, 110 and 220,
+    // so the short third scene is closer to the second one
+    let clip = clip(vec![
+        Segment::Scene { frames: 20 },
+        Segment::Scene { frames: 20 },
+        Segment::Scene { frames: 6 },
+        Segment::Scene { frames: 20 },
+    ]);
+    assert_eq!(detect(&clip, fast()), vec![0, 20, 40, 46]);
+    assert_eq!(
+        detect(
+            &clip,
+            DetectionOptions {
+                min_scene_length: Some(10),
+                ..fast()
+            }
+        ),
+        vec![0, 20, 46]
+    );
+    // `min_scenecut_distance` always keeps the earlier scene change
+    assert_eq!(
+        detect(
+            &clip,
+            DetectionOptions {
+                min_scenecut_distance: Some(10),
+                ..fast()
+            }
+        ),
+        vec![0, 20, 40]
+    );
+}
+
+#[test]
+fn merges_last_scene_into_previous() {
+    let clip = clip(vec![
+        Segment::Scene { frames: 20 },
+        Segment::Scene { frames: 20 },
+        Segment::Scene { frames: 8 },
+    ]);
+    assert_eq!(
+        detect(
+            &clip,
+            DetectionOptions {
+                min_scene_length: Some(10),
+                ..fast()
+            }
+        ),
+        vec![0, 20]
+    );
+}
+
+#[test]
+fn splits_long_scenes_at_lowest_score() {
+    // Static scenes have no differences between frames,
+    // so only the flash and the frame after it have non-zero scores
+    let clip = clip(vec![
+        Segment::Static { frames: 27 },
+        Segment::Flash { frames: 1 },
+        Segment::Continue { frames: 32 },
+    ]);
+    let opts = DetectionOptions {
+        max_scene_length: Some(30),
+        scene_split_window: Some(5),
+        ..fast()
+    };
+    assert_eq!(detect(&clip, fast()), vec![0]);
+    // 25 to 30 are candidates for the first split, where 27 and 28 are
+    // the flash. The remaining scene of 30 frames does not need a split.
+    assert_eq!(detect(&clip, opts), vec![0, 30]);
+    // `max_scenecut_distance` places splits exactly at the limit
+    assert_eq!(
+        detect(
+            &clip,
+            DetectionOptions {
+                max_scenecut_distance: Some(28),
+                ..fast()
+            }
+        ),
+        vec![0, 28, 56]
+    );
+}
+
+#[test]
+fn splits_moving_scenes() {
+    let clip = clip(vec![Segment::Scene { frames: 100 }]);
+    let opts = DetectionOptions {
+        max_scene_length: Some(30),
+        ..fast()
+    };
+    let scene_changes = detect(&clip, opts);
+    assert_eq!(scene_changes.len(), 4);
+    for pair in scene_changes.windows(2) {
+        let length = pair[1] - pair[0];
+        assert!((23..=30).contains(&length), "{scene_changes:?}");
+    }
+}
+
+#[test]
+fn target_scene_count() {
+    // The luma of the scenes is 50, 180, 110 and 220,
+    // so the scene changes from strongest to weakest are 20, 60 and 40
+    let clip = clip(vec![
+        Segment::Scene { frames: 20 },
+        Segment::Scene { frames: 20 },
+        Segment::Scene { frames: 20 },
+        Segment::Scene { frames: 20 },
+    ]);
+    let target = |target_scene_count, min_scenecut_distance| DetectionOptions {
+        target_scene_count: Some(target_scene_count),
+        min_scenecut_distance,
+        ..fast()
+    };
+    assert_eq!(detect(&clip, target(4, None)), vec![0, 20, 40, 60]);
+    assert_eq!(detect(&clip, target(3, None)), vec![0, 20, 60]);
+    assert_eq!(detect(&clip, target(1, None)), vec![0]);
+    // The stronger scene changes are too close to the start or end of the clip,
+    // and no other frame is far enough from all of them
+    assert_eq!(detect(&clip, target(3, Some(25))), vec![0, 40]);
+}
+
+#[test]
+fn forced_and_forbidden_cuts() {
+    let clip = clip(vec![Segment::Scene { frames: 20 }; 4]);
+    let overrides = FrameOverrides {
+        forced_cuts: [10].into(),
+        forbidden_ranges: vec![35..45, 50..55],
+        ..FrameOverrides::default()
+    };
+    assert_eq!(
+        detect_with_overrides(&clip, fast(), &overrides),
+        vec![0, 10, 20, 60]
+    );
+    // Forced cuts count towards the minimum distance
+    assert_eq!(
+        detect_with_overrides(
+            &clip,
+            DetectionOptions {
+                min_scenecut_distance: Some(15),
+                ..fast()
+            },
+            &overrides
+        ),
+        vec![0, 10, 60]
+    );
+    // and are never merged away, even at the start of the clip
+    assert_eq!(
+        detect_with_overrides(
+            &clip,
+            DetectionOptions {
+                min_scene_length: Some(10),
+                ..fast()
+            },
+            &FrameOverrides {
+                forced_cuts: [5].into(),
+                ..overrides.clone()
+            }
+        ),
+        vec![0, 5, 20, 60]
+    );
+    // Forced cuts are picked first and count towards the target
+    assert_eq!(
+        detect_with_overrides(
+            &clip,
+            DetectionOptions {
+                target_scene_count: Some(3),
+                ..fast()
+            },
+            &overrides
+        ),
+        vec![0, 10, 20]
+    );
+    // Forced cuts take precedence over forbidden ranges
+    assert_eq!(
+        detect_with_overrides(
+            &clip,
+            fast(),
+            &FrameOverrides {
+                forced_cuts: [40].into(),
+                ..overrides
+            }
+        ),
+        vec![0, 20, 40, 60]
+    );
+}
+
+#[test]
+fn splits_outside_forbidden_ranges() {
+    let clip = clip(vec![Segment::Scene { frames: 100 }]);
+    let opts = DetectionOptions {
+        max_scene_length: Some(40),
+        ..fast()
+    };
+    let overrides = FrameOverrides {
+        forbidden_ranges: vec![20..60, 90..100],
+        ..FrameOverrides::default()
+    };
+    // The window before the first limit is forbidden, so the split moves
+    // before the range. No frame before the next limit is allowed,
+    // so the scene after it runs past the limit to the end of the range.
+    assert_eq!(
+        detect_with_overrides(&clip, opts, &overrides),
+        vec![0, 19, 60]
+    );
+}
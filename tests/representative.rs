@@ -0,0 +1,77 @@
+mod common;
+
+use av_scenechange::{
+    synthetic::Segment, DetectionOptions, DetectionResults, FrameOverrides, RepresentativeFrame,
+    SceneDetectionSpeed,
+};
+use common::{clip, detect_results};
+
+fn detect(segments: Vec<Segment>, opts: DetectionOptions) -> DetectionResults {
+    detect_results(&clip(segments), opts, &FrameOverrides::default())
+}
+
+fn fast(representative_frame: RepresentativeFrame) -> DetectionOptions {
+    DetectionOptions {
+        analysis_speed: SceneDetectionSpeed::Fast,
+        representative_frame: Some(representative_frame),
+        ..DetectionOptions::default()
+    }
+}
+
+#[test]
+fn not_reported_by_default() {
+    let results = detect(
+        vec![Segment::Scene { frames: 10 }],
+        DetectionOptions::default(),
+    );
+    assert_eq!(results.representative_frames, None);
+}
+
+#[test]
+fn avoids_flashes_and_fades() {
+    let segments = vec![
+        Segment::Scene { frames: 10 },
+        Segment::Flash { frames: 2 },
+        Segment::Continue { frames: 10 },
+        Segment::Scene { frames: 20 },
+        Segment::FadeOut { frames: 10 },
+    ];
+    for method in [
+        RepresentativeFrame::Histogram,
+        RepresentativeFrame::Sharpness,
+    ] {
+        let results = detect(segments.clone(), fast(method));
+        assert_eq!(results.scene_changes, vec![0, 22], "{method:?}");
+        let representative_frames = results.representative_frames.unwrap();
+        assert!(
+            !(10..12).contains(&representative_frames[0]),
+            "{method:?} {representative_frames:?}"
+        );
+        assert!(
+            (22..42).contains(&representative_frames[1]),
+            "{method:?} {representative_frames:?}"
+        );
+    }
+}
+
+#[test]
+fn follows_post_processing() {
+    let results = detect(
+        vec![Segment::Scene { frames: 40 }, Segment::Scene { frames: 40 }],
+        DetectionOptions {
+            max_scene_length: Some(20),
+            ..fast(RepresentativeFrame::Sharpness)
+        },
+    );
+    let representative_frames = results.representative_frames.unwrap();
+    assert_eq!(representative_frames.len(), results.scene_changes.len());
+    for (i, &frameno) in representative_frames.iter().enumerate() {
+        let start = results.scene_changes[i];
+        let end = results
+            .scene_changes
+            .get(i + 1)
+            .copied()
+            .unwrap_or(results.frame_count);
+        assert!((start..end).contains(&frameno));
+    }
+}
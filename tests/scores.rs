@@ -0,0 +1,89 @@
+mod common;
+
+use av_scenechange::{
+    decoder::Decoder,
+    score_frames,
+    synthetic::{Segment, SyntheticClip},
+    Algorithm, DetectionOptions, FrameScores, HsvWeights,
+};
+
+fn scores(bit_depth: usize) -> FrameScores {
+    let clip = SyntheticClip {
+        bit_depth,
+        ..common::clip(vec![
+            Segment::Scene { frames: 20 },
+            Segment::Scene { frames: 20 },
+            Segment::Flash { frames: 1 },
+            Segment::Continue { frames: 10 },
+        ])
+    };
+    let hsv_weights = HsvWeights {
+        hue: 0.5,
+        ..HsvWeights::default()
+    };
+    common::score(&clip, hsv_weights)
+}
+
+#[test]
+fn round_trip() {
+    for bit_depth in [8, 10] {
+        let scores = scores(bit_depth);
+        let mut data = Vec::new();
+        scores.write_to(&mut data).unwrap();
+        let read = FrameScores::read_from(data.as_slice()).unwrap();
+
+        assert_eq!(read.bit_depth, bit_depth);
+        assert_eq!(read.frame_count(), 51);
+        assert_eq!(read.pixel_difference, scores.pixel_difference);
+        assert_eq!(read.histogram, scores.histogram);
+        assert_eq!(read.hsv, scores.hsv);
+        assert!((read.hsv_weights.hue - 0.5).abs() < f64::EPSILON);
+
+        let opts = DetectionOptions {
+            algorithm: Algorithm::Histogram,
+            ..common::fast()
+        };
+        assert_eq!(read.scene_changes(opts), vec![0, 20]);
+    }
+}
+
+#[test]
+fn invalid_data() {
+    let mut data = Vec::new();
+    scores(8).write_to(&mut data).unwrap();
+
+    // Truncated
+    assert!(FrameScores::read_from(&data[..data.len() - 1]).is_err());
+    // Not a scores file
+    assert!(FrameScores::read_from(&b"YUV4MPEG2 W64 H64"[..]).is_err());
+    // Unknown version
+    data[8] = 2;
+    assert!(FrameScores::read_from(data.as_slice()).is_err());
+}
+
+#[test]
+fn hue_wraps_around() {
+    // Two solid red frames, one just under a full turn of hue (255)
+    // and one just over it (1)
+    let (width, height) = (64, 64);
+    let mut data = format!("YUV4MPEG2 W{width} H{height} F30:1 Ip A1:1 C444\n").into_bytes();
+    for (y, u, v) in [(100, 104, 200), (100, 109, 180)] {
+        data.extend_from_slice(b"FRAME\n");
+        for value in [y, u, v] {
+            data.extend(std::iter::repeat_n(value, width * height));
+        }
+    }
+    let mut reader = data.as_slice();
+    let mut dec = Decoder::Y4m(y4m::Decoder::new(&mut reader).unwrap());
+    let hsv_weights = HsvWeights {
+        hue: 1.0,
+        saturation: 0.0,
+        value: 0.0,
+    };
+    let scores = score_frames::<_, u8>(&mut dec, hsv_weights, None, None).unwrap();
+    assert!(
+        (scores.hsv[1] - 2.0).abs() < f64::EPSILON,
+        "{:?}",
+        scores.hsv
+    );
+}
@@ -0,0 +1,116 @@
+#![cfg(feature = "serialize")]
+
+mod common;
+
+use std::path::{Path, PathBuf};
+
+use av_scenechange::{serve::serve, synthetic::Segment};
+use serde_json::Value;
+
+fn write_clip(name: &str) -> PathBuf {
+    let data = common::clip(vec![Segment::Scene { frames: 10 }; 3])
+        .to_y4m()
+        .unwrap();
+    let path = Path::new(env!("CARGO_TARGET_TMPDIR")).join(name);
+    std::fs::write(&path, data).unwrap();
+    path
+}
+
+/// Serves `input`, returning the parsed response lines
+fn serve_lines(input: &str) -> Vec<Value> {
+    let mut output = Vec::new();
+    serve(input.as_bytes(), &mut output).unwrap();
+    String::from_utf8(output)
+        .unwrap()
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect()
+}
+
+#[test]
+fn responds_to_each_job_in_order() {
+    let path = write_clip("serve.y4m");
+    let missing = Path::new(env!("CARGO_TARGET_TMPDIR")).join("serve-missing.y4m");
+    let input = format!(
+        "{}\n\n{{not json\n{}\n{}\n",
+        serde_json::json!({
+            "id": "good",
+            "input": path,
+            "options": { "analysis_speed": "Fast" },
+        }),
+        serde_json::json!({ "id": "missing", "input": missing }),
+        serde_json::json!({
+            "id": "forced",
+            "input": path,
+            "options": { "analysis_speed": "Fast" },
+            "overrides": { "forced_cuts": [5] },
+            "frame_limit": 15,
+        }),
+    );
+    let responses = serve_lines(&input);
+    // Blank lines are skipped, a malformed line still gets a response
+    assert_eq!(responses.len(), 4, "{responses:?}");
+
+    assert_eq!(responses[0]["id"], "good");
+    assert_eq!(
+        responses[0]["results"]["scene_changes"],
+        serde_json::json!([0, 10, 20])
+    );
+    assert_eq!(responses[0]["results"]["frame_count"], 30);
+    assert!(responses[0].get("error").is_none());
+
+    assert_eq!(responses[1]["id"], Value::Null);
+    assert!(responses[1].get("results").is_none());
+    assert!(responses[1]["error"]
+        .as_str()
+        .unwrap()
+        .starts_with("Invalid job"));
+
+    assert_eq!(responses[2]["id"], "missing");
+    assert!(responses[2].get("results").is_none());
+    assert!(responses[2]["error"].is_string());
+
+    assert_eq!(responses[3]["id"], "forced");
+    assert_eq!(
+        responses[3]["results"]["scene_changes"],
+        serde_json::json!([0, 5, 10])
+    );
+    assert_eq!(responses[3]["results"]["frame_count"], 15);
+}
+
+#[test]
+fn bad_jobs_do_not_stop_the_server() {
+    let path = write_clip("serve-bad.y4m");
+    let job = |id: &str, options: Value| {
+        serde_json::json!({ "id": id, "input": path, "options": options }).to_string()
+    };
+    let input = [
+        job("zero", serde_json::json!({ "lookahead_distance": 0 })),
+        job(
+            "huge",
+            serde_json::json!({ "lookahead_distance": u64::MAX }),
+        ),
+        job("typo", serde_json::json!({ "analysis_sped": "Fast" })),
+        serde_json::json!({ "id": "extra", "input": path, "thumbnails": true }).to_string(),
+        job("good", serde_json::json!({ "analysis_speed": "Fast" })),
+    ]
+    .join("\n");
+    let responses = serve_lines(&input);
+    assert_eq!(responses.len(), 5, "{responses:?}");
+
+    for response in &responses[..2] {
+        assert!(response.get("results").is_none());
+        let error = response["error"].as_str().unwrap();
+        assert!(error.contains("lookahead_distance"), "{error}");
+    }
+    for response in &responses[2..4] {
+        assert!(response.get("results").is_none());
+        let error = response["error"].as_str().unwrap();
+        assert!(error.contains("unknown field"), "{error}");
+    }
+    assert_eq!(responses[4]["id"], "good");
+    assert_eq!(
+        responses[4]["results"]["scene_changes"],
+        serde_json::json!([0, 10, 20])
+    );
+}
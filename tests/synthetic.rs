@@ -0,0 +1,134 @@
+mod common;
+
+use av_scenechange::{
+    decoder::Decoder,
+    detect_scene_changes, score_frames,
+    synthetic::{Segment, SyntheticClip},
+    Algorithm, DetectionOptions, HsvWeights, FAST_PIXEL_THRESHOLD,
+};
+use common::detect;
+use rav1e::prelude::ChromaSampling;
+
+const LAYOUTS: [(usize, ChromaSampling); 11] = [
+    (8, ChromaSampling::Cs420),
+    (10, ChromaSampling::Cs420),
+    (12, ChromaSampling::Cs420),
+    (8, ChromaSampling::Cs422),
+    (10, ChromaSampling::Cs422),
+    (12, ChromaSampling::Cs422),
+    (8, ChromaSampling::Cs444),
+    (10, ChromaSampling::Cs444),
+    (12, ChromaSampling::Cs444),
+    (8, ChromaSampling::Cs400),
+    (12, ChromaSampling::Cs400),
+];
+
+fn clip(bit_depth: usize, chroma_sampling: ChromaSampling) -> SyntheticClip {
+    SyntheticClip {
+        bit_depth,
+        chroma_sampling,
+        ..common::clip(vec![
+            Segment::Scene { frames: 30 },
+            Segment::Scene { frames: 30 },
+            Segment::Flash { frames: 2 },
+            Segment::Continue { frames: 28 },
+            Segment::Static { frames: 30 },
+            Segment::FadeOut { frames: 20 },
+            Segment::Scene { frames: 30 },
+        ])
+    }
+}
+
+#[test]
+fn expected_scene_changes() {
+    let clip = clip(8, ChromaSampling::Cs420);
+    assert_eq!(clip.frame_count(), 170);
+    assert_eq!(clip.scene_changes(), vec![0, 30, 90, 140]);
+}
+
+#[test]
+fn unsupported_layout() {
+    assert!(clip(10, ChromaSampling::Cs400).to_y4m().is_err());
+    assert!(clip(16, ChromaSampling::Cs420).to_y4m().is_err());
+}
+
+#[test]
+fn fast_every_layout() {
+    for (bit_depth, chroma_sampling) in LAYOUTS {
+        // Like rav1e, the threshold is only scaled by `bit_depth / 8`,
+        // while pixel values double with each extra bit
+        let opts = DetectionOptions {
+            fast_pixel_threshold: FAST_PIXEL_THRESHOLD * f64::from(1u32 << (bit_depth - 8)) * 8.0
+                / bit_depth as f64,
+            ..common::fast()
+        };
+        let clip = clip(bit_depth, chroma_sampling);
+        assert_eq!(
+            detect(&clip, opts),
+            clip.scene_changes(),
+            "{bit_depth}-bit {chroma_sampling:?}"
+        );
+    }
+}
+
+#[test]
+fn standard() {
+    // rav1e's standard detector reports the frame after a flash as a scene change,
+    // so this clip has no flashes
+    for (bit_depth, chroma_sampling) in [(8, ChromaSampling::Cs420), (10, ChromaSampling::Cs444)] {
+        let clip = SyntheticClip {
+            bit_depth,
+            chroma_sampling,
+            ..common::clip(vec![
+                Segment::Scene { frames: 30 },
+                Segment::Static { frames: 30 },
+                Segment::Continue { frames: 10 },
+                Segment::FadeOut { frames: 20 },
+                Segment::Scene { frames: 30 },
+            ])
+        };
+        assert_eq!(
+            detect(&clip, DetectionOptions::default()),
+            clip.scene_changes(),
+            "{bit_depth}-bit {chroma_sampling:?}"
+        );
+    }
+}
+
+#[test]
+fn native_algorithms() {
+    for algorithm in [
+        Algorithm::PixelDifference,
+        Algorithm::Histogram,
+        Algorithm::Adaptive,
+        Algorithm::Hsv,
+    ] {
+        let opts = DetectionOptions {
+            algorithm,
+            ..common::fast()
+        };
+        let clip = clip(8, ChromaSampling::Cs420);
+        assert_eq!(detect(&clip, opts), clip.scene_changes(), "{algorithm:?}");
+    }
+}
+
+#[test]
+fn corrupt_frames_are_errors() {
+    let mut data = clip(8, ChromaSampling::Cs420).to_y4m().unwrap();
+    // Break the header of the third frame
+    let header = data
+        .windows(6)
+        .enumerate()
+        .filter(|(_, window)| window == b"FRAME\n")
+        .nth(2)
+        .unwrap()
+        .0;
+    data[header] = b'X';
+
+    let mut reader = data.as_slice();
+    let mut dec = Decoder::Y4m(y4m::Decoder::new(&mut reader).unwrap());
+    assert!(detect_scene_changes::<_, u8>(&mut dec, common::fast(), None, None).is_err());
+    let mut reader = data.as_slice();
+    let mut dec = Decoder::Y4m(y4m::Decoder::new(&mut reader).unwrap());
+    assert!(score_frames::<_, u8>(&mut dec, HsvWeights::default(), None, None).is_err());
+}
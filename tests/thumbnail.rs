@@ -0,0 +1,156 @@
+#![cfg(feature = "image")]
+
+mod common;
+
+use av_scenechange::{
+    decoder::Decoder,
+    synthetic::{Segment, SyntheticClip},
+    thumbnail::{
+        contact_sheet, detect_with_thumbnails, Thumbnail, ThumbnailFrame, ThumbnailOptions,
+    },
+    DetectionOptions, DetectionResults, FrameOverrides,
+};
+use common::{clip, fast};
+
+/// Runs detection with thumbnails, checking that it finds the same
+/// scene changes as detection without them
+fn detect(
+    clip: &SyntheticClip,
+    opts: DetectionOptions,
+    frame: ThumbnailFrame,
+) -> (DetectionResults, Vec<Thumbnail>) {
+    let data = clip.to_y4m().unwrap();
+    let mut reader = data.as_slice();
+    let mut dec = Decoder::Y4m(y4m::Decoder::new(&mut reader).unwrap());
+    let thumbnail_opts = ThumbnailOptions {
+        frame,
+        ..ThumbnailOptions::default()
+    };
+    let (results, thumbnails) = detect_with_thumbnails::<_, u8>(
+        &mut dec,
+        opts,
+        &FrameOverrides::default(),
+        thumbnail_opts,
+        None,
+        None,
+    )
+    .unwrap();
+    assert_eq!(results.scene_changes, common::detect(clip, opts));
+    (results, thumbnails)
+}
+
+fn frames(thumbnails: &[Thumbnail]) -> Vec<usize> {
+    thumbnails
+        .iter()
+        .map(|thumbnail| thumbnail.frameno)
+        .collect()
+}
+
+fn mean_luma(thumbnail: &Thumbnail) -> f64 {
+    let pixels = thumbnail.image.pixels();
+    let count = pixels.len() as f64;
+    pixels
+        .map(|pixel| pixel.0.iter().map(|&value| f64::from(value)).sum::<f64>() / 3.0)
+        .sum::<f64>()
+        / count
+}
+
+#[test]
+fn picks_frame_of_each_scene() {
+    let clip = clip(vec![
+        Segment::Scene { frames: 20 },
+        Segment::Scene { frames: 30 },
+        Segment::Scene { frames: 300 },
+    ]);
+
+    let (results, thumbnails) = detect(&clip, fast(), ThumbnailFrame::First);
+    assert_eq!(results.scene_changes, vec![0, 20, 50]);
+    assert_eq!(frames(&thumbnails), vec![0, 20, 50]);
+    for thumbnail in &thumbnails {
+        assert_eq!(
+            (thumbnail.image.width(), thumbnail.image.height()),
+            (128, 96)
+        );
+    }
+    // The luma of the scenes is 50, 180 and 110
+    let luma = thumbnails.iter().map(mean_luma).collect::<Vec<_>>();
+    assert!(luma[0] < luma[2] && luma[2] < luma[1], "{luma:?}");
+
+    let (_, thumbnails) = detect(&clip, fast(), ThumbnailFrame::Middle);
+    let middle = frames(&thumbnails);
+    assert_eq!(middle[..2], [10, 35]);
+    // Only some frames of long scenes are kept
+    assert!(middle[2].abs_diff(200) <= 10, "{middle:?}");
+
+    let (_, thumbnails) = detect(&clip, fast(), ThumbnailFrame::Sharpest);
+    for (thumbnail, scene) in thumbnails.iter().zip([0..20, 20..50, 50..350]) {
+        assert!(scene.contains(&thumbnail.frameno));
+    }
+}
+
+#[test]
+fn sharpest_avoids_fades() {
+    let clip = clip(vec![
+        Segment::FadeOut { frames: 10 },
+        Segment::Scene { frames: 20 },
+        Segment::FadeOut { frames: 10 },
+    ]);
+    let (results, thumbnails) = detect(&clip, fast(), ThumbnailFrame::Sharpest);
+    assert_eq!(results.scene_changes, vec![0, 10]);
+    assert!(thumbnails[1].frameno < 30);
+}
+
+#[test]
+fn merged_scenes_share_thumbnail() {
+    let clip = clip(vec![
+        Segment::Scene { frames: 20 },
+        Segment::Scene { frames: 20 },
+        Segment::Scene { frames: 6 },
+        Segment::Scene { frames: 20 },
+    ]);
+    let opts = DetectionOptions {
+        min_scene_length: Some(10),
+        ..fast()
+    };
+    let (results, thumbnails) = detect(&clip, opts, ThumbnailFrame::First);
+    assert_eq!(results.scene_changes, vec![0, 20, 46]);
+    assert_eq!(frames(&thumbnails), vec![0, 20, 46]);
+
+    let (_, thumbnails) = detect(&clip, opts, ThumbnailFrame::Middle);
+    assert_eq!(frames(&thumbnails), vec![10, 30, 56]);
+}
+
+#[test]
+fn contact_sheet_grid() {
+    let clip = clip(vec![
+        Segment::Scene { frames: 10 },
+        Segment::Scene { frames: 10 },
+        Segment::Scene { frames: 10 },
+    ]);
+    let (_, thumbnails) = detect(&clip, fast(), ThumbnailFrame::First);
+    let sheet = contact_sheet(&thumbnails, 2, 64).unwrap();
+    assert_eq!((sheet.width(), sheet.height()), (128, 96));
+    // The empty last tile stays black
+    assert_eq!(sheet.get_pixel(127, 95).0, [0, 0, 0]);
+    assert!(contact_sheet(&[], 2, 64).is_err());
+}
+
+#[test]
+fn rejects_scene_changes_added_after_detection() {
+    let data = clip(vec![Segment::Scene { frames: 10 }]).to_y4m().unwrap();
+    let mut reader = data.as_slice();
+    let mut dec = Decoder::Y4m(y4m::Decoder::new(&mut reader).unwrap());
+    let opts = DetectionOptions {
+        target_scene_count: Some(2),
+        ..fast()
+    };
+    assert!(detect_with_thumbnails::<_, u8>(
+        &mut dec,
+        opts,
+        &FrameOverrides::default(),
+        ThumbnailOptions::default(),
+        None,
+        None
+    )
+    .is_err());
+}
@@ -0,0 +1,112 @@
+mod common;
+
+use av_scenechange::{
+    synthetic::{Segment, SyntheticClip},
+    tune::{sweep, SweepRanges},
+    Algorithm, ContentTune, DetectionOptions, HsvWeights, SceneDetectionSpeed,
+    FAST_PIXEL_THRESHOLD,
+};
+
+fn clip() -> SyntheticClip {
+    common::clip(vec![
+        Segment::Scene { frames: 20 },
+        Segment::Scene { frames: 8 },
+        Segment::Scene { frames: 20 },
+        Segment::Flash { frames: 2 },
+        Segment::Continue { frames: 10 },
+        Segment::Static { frames: 15 },
+        Segment::FadeOut { frames: 10 },
+        Segment::Scene { frames: 20 },
+    ])
+}
+
+#[test]
+fn replay_matches_detection() {
+    let clip = clip();
+    let scores = common::score(&clip, HsvWeights::default());
+    assert_eq!(scores.frame_count(), clip.frame_count());
+
+    for algorithm in [
+        Algorithm::PixelDifference,
+        Algorithm::Histogram,
+        Algorithm::Adaptive,
+        Algorithm::Hsv,
+    ] {
+        for (detect_flashes, lookahead_distance, min_scenecut_distance, threshold_scale) in [
+            (true, 5, None, 1.0),
+            (true, 2, None, 0.5),
+            (false, 1, None, 1.0),
+            (true, 5, Some(10), 0.1),
+        ] {
+            let opts = DetectionOptions {
+                analysis_speed: SceneDetectionSpeed::Fast,
+                algorithm,
+                detect_flashes,
+                lookahead_distance,
+                min_scenecut_distance,
+                max_scenecut_distance: Some(30),
+                threshold_scale,
+                ..DetectionOptions::default()
+            };
+            assert_eq!(
+                scores.scene_changes(opts),
+                common::detect(&clip, opts),
+                "{opts:?}"
+            );
+        }
+    }
+}
+
+#[test]
+fn sweep_finds_ground_truth() {
+    let clip = clip();
+    let scores = common::score(&clip, HsvWeights::default());
+    let results = sweep(
+        &scores,
+        DetectionOptions::default(),
+        &SweepRanges::default(),
+        &clip.scene_changes(),
+        0,
+    );
+    let best = &results[0];
+    assert!((best.evaluation.f1 - 1.0).abs() < f64::EPSILON);
+    assert_eq!(best.options.analysis_speed, SceneDetectionSpeed::Fast);
+    // The flash is only ignored with flash detection
+    assert!(best.options.detect_flashes);
+    assert!(results
+        .windows(2)
+        .all(|pair| pair[0].evaluation.f1 >= pair[1].evaluation.f1));
+}
+
+#[test]
+fn sweep_finds_max_scenecut_distance() {
+    // A scene without any visible changes, split every 60 frames
+    let clip = common::clip(vec![Segment::Scene { frames: 150 }]);
+    let scores = common::score(&clip, HsvWeights::default());
+    let results = sweep(
+        &scores,
+        DetectionOptions::default(),
+        &SweepRanges::default(),
+        &[0, 60, 120],
+        0,
+    );
+    let best = &results[0];
+    assert!((best.evaluation.f1 - 1.0).abs() < f64::EPSILON);
+    assert_eq!(best.options.max_scenecut_distance, Some(60));
+}
+
+#[test]
+fn content_tune_keeps_threshold_adjustments() {
+    let opts = DetectionOptions {
+        threshold_scale: 2.0,
+        threshold_bias: 1.5,
+        ..DetectionOptions::default()
+    };
+    let animation = opts.with_tune(ContentTune::Animation);
+    assert!(animation.fast_pixel_threshold < FAST_PIXEL_THRESHOLD);
+    assert!((animation.threshold_scale - 2.0).abs() < f64::EPSILON);
+    assert!((animation.threshold_bias - 1.5).abs() < f64::EPSILON);
+    let live_action = animation.with_tune(ContentTune::LiveAction);
+    assert!((live_action.fast_pixel_threshold - FAST_PIXEL_THRESHOLD).abs() < f64::EPSILON);
+    assert!((live_action.threshold_scale - 2.0).abs() < f64::EPSILON);
+}
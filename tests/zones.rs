@@ -0,0 +1,75 @@
+mod common;
+
+use av_scenechange::{
+    synthetic::{Segment, SyntheticClip},
+    DetectionOptions, FrameOverrides, SceneDetectionSpeed, Zone,
+};
+use common::{detect_with_overrides, fast};
+
+fn clip() -> SyntheticClip {
+    common::clip(vec![Segment::Scene { frames: 20 }; 6])
+}
+
+fn detect(clip: &SyntheticClip, opts: DetectionOptions, zones: Vec<Zone>) -> Vec<usize> {
+    let overrides = FrameOverrides {
+        zones,
+        ..FrameOverrides::default()
+    };
+    detect_with_overrides(clip, opts, &overrides)
+}
+
+#[test]
+fn zones_override_options() {
+    let clip = clip();
+    assert_eq!(detect(&clip, fast(), Vec::new()), clip.scene_changes());
+    // Only scene changes inside the zone are affected
+    let zone = Zone {
+        start: 50,
+        end: 90,
+        threshold_scale: Some(100.0),
+        ..Zone::default()
+    };
+    assert_eq!(detect(&clip, fast(), vec![zone]), vec![0, 20, 40, 100]);
+    let zone = Zone {
+        start: 30,
+        end: 70,
+        min_scenecut_distance: Some(30),
+        ..Zone::default()
+    };
+    assert_eq!(detect(&clip, fast(), vec![zone]), vec![0, 20, 60, 80, 100]);
+    // The earlier of two overlapping zones is used, so only scene changes
+    // at least 30 frames apart in the first zone are kept
+    let zones = vec![
+        zone,
+        Zone {
+            start: 0,
+            end: 120,
+            threshold_scale: Some(100.0),
+            ..Zone::default()
+        },
+    ];
+    assert_eq!(detect(&clip, fast(), zones), vec![0, 40]);
+}
+
+#[test]
+fn zones_switch_detectors() {
+    let clip = clip();
+    // Detectors restarting in the middle of a scene don't add scene changes
+    let zone = Zone {
+        start: 30,
+        end: 90,
+        analysis_speed: Some(SceneDetectionSpeed::Fast),
+        ..Zone::default()
+    };
+    assert_eq!(
+        detect(&clip, DetectionOptions::default(), vec![zone]),
+        clip.scene_changes()
+    );
+    let zone = Zone {
+        start: 10,
+        end: 50,
+        detect_flashes: Some(false),
+        ..Zone::default()
+    };
+    assert_eq!(detect(&clip, fast(), vec![zone]), clip.scene_changes());
+}